@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use chrono::NaiveDate;
+
+use crate::{Appointment, LazyLevel, MyRegistrations, Semester, SemesterResult, Stine, SubModule};
+
+/// # Backend
+/// Source of the data of a [`Stine`] session.
+/// Allows switching between scraping the website ([`HtmlBackend`])
+/// and the xml api of the mobile app (`mobile::MobileBackend`)
+pub trait Backend {
+    /// Returns the registration status of the applied modules.
+    /// See [`Stine::get_my_registrations`]
+    fn get_my_registrations(&self, stine: &mut Stine, lazy: LazyLevel)
+                            -> Result<MyRegistrations, anyhow::Error>;
+
+    /// Returns exam and semester results of selected semesters.
+    /// See [`Stine::get_semester_results`]
+    fn get_semester_results(&self, stine: &Stine, semesters: Vec<Semester>, lazy: LazyLevel)
+                            -> Result<Vec<SemesterResult>, anyhow::Error>;
+
+    /// Returns all exam and semester results.
+    /// See [`Stine::get_all_semester_results`]
+    fn get_all_semester_results(&self, stine: &Stine, lazy: LazyLevel)
+                                -> Result<Vec<SemesterResult>, anyhow::Error>;
+
+    /// Returns the events (courses) you are registered for, accepted or pending
+    fn get_events(&self, stine: &mut Stine, lazy: LazyLevel) -> Result<Vec<SubModule>, anyhow::Error>;
+
+    /// Returns the appointments of all registered courses taking place in `range`,
+    /// mapped by the course id
+    fn get_appointments(&self, stine: &mut Stine, range: RangeInclusive<NaiveDate>)
+                        -> Result<HashMap<String, Vec<Appointment>>, anyhow::Error>;
+}
+
+/// Scrapes the data from the STINE website. Default backend
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HtmlBackend;
+
+impl Backend for HtmlBackend {
+    fn get_my_registrations(&self, stine: &mut Stine, lazy: LazyLevel)
+                            -> Result<MyRegistrations, anyhow::Error> {
+        stine.get_my_registrations(lazy)
+    }
+
+    fn get_semester_results(&self, stine: &Stine, semesters: Vec<Semester>, lazy: LazyLevel)
+                            -> Result<Vec<SemesterResult>, anyhow::Error> {
+        Ok(stine.get_semester_results(semesters, lazy)?)
+    }
+
+    fn get_all_semester_results(&self, stine: &Stine, lazy: LazyLevel)
+                                -> Result<Vec<SemesterResult>, anyhow::Error> {
+        Ok(stine.get_all_semester_results(lazy)?)
+    }
+
+    fn get_events(&self, stine: &mut Stine, lazy: LazyLevel) -> Result<Vec<SubModule>, anyhow::Error> {
+        let registrations = stine.get_my_registrations(lazy)?;
+        Ok(registrations.accepted_submodules.into_iter().chain(registrations.pending_submodules).collect())
+    }
+
+    /// **Note**: Makes an extra request for every registered course
+    fn get_appointments(&self, stine: &mut Stine, range: RangeInclusive<NaiveDate>)
+                        -> Result<HashMap<String, Vec<Appointment>>, anyhow::Error> {
+        let registrations = stine.get_my_registrations(LazyLevel::FullLazy)?;
+
+        let mut appointments: HashMap<String, Vec<Appointment>> = HashMap::new();
+        for mut submodule in registrations.accepted_submodules.into_iter()
+            .chain(registrations.pending_submodules) {
+            let in_range: Vec<Appointment> = submodule.try_appointments(stine)?.unwrap_or_default()
+                .into_iter()
+                .filter(|a| a.from.is_some_and(|from| range.contains(&from.date_naive())))
+                .collect();
+
+            if !in_range.is_empty() {
+                appointments.entry(submodule.id).or_default().extend(in_range);
+            }
+        }
+
+        Ok(appointments)
+    }
+}
@@ -8,6 +8,7 @@ clippy::nursery,
 
 #![allow(clippy::use_self)]
 
+pub use backend::*;
 pub use stine::*;
 pub use types::document::*;
 pub use types::event::*;
@@ -15,6 +16,7 @@ pub use types::language::*;
 pub use types::period::*;
 pub use types::semester::*;
 
+mod backend;
 mod stine;
 
 mod types;
@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use chrono::NaiveDate;
+
+use crate::{Appointment, Backend, CourseInfo, CourseResult, HtmlBackend, Lazy, LazyLevel, LazyLoaded,
+            MyRegistrations, Semester, SemesterResult, Stine, SubModule};
+use crate::mobile::{ExamGrade, ExamHistory, StudentEvent, StudentExam};
+use crate::parse::parse_instructors;
+
+/// # MobileBackend
+/// Fetches the data from the xml api of the STINE mobile app (`ACTIONMOBILE`),
+/// which is more stable than scraping the website.
+/// Every call falls back to the [`HtmlBackend`] if the mobile api fails.
+///
+/// **Note**: The mobile api does not provide everything the website does:
+/// * [`MyRegistrations::rejected_submodules`] and [`MyRegistrations::accepted_modules`] are always empty
+/// * [`SubModule`]s without cache entry have no groups
+/// * [`SemesterResult`]s have no credits, gpa or grade stats
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MobileBackend;
+
+fn or_html<T>(mobile: Result<T, anyhow::Error>, html: impl FnOnce() -> Result<T, anyhow::Error>)
+              -> Result<T, anyhow::Error> {
+    mobile.or_else(|err| {
+        log::warn!("Mobile api request failed, falling back to html: {err}");
+        html()
+    })
+}
+
+impl From<&StudentEvent> for CourseInfo {
+    fn from(event: &StudentEvent) -> Self {
+        CourseInfo {
+            event_type: event.event_category,
+            event_type_raw: event.event_type.clone(),
+            instructors: event.instructors_string.clone().map(parse_instructors),
+            timetable_name: None,
+            hours_per_week: event.hours_per_week,
+            credits: event.credits.map(|c| c.to_string()),
+            language: event.language.clone(),
+            min_participants: None,
+            max_participants: event.max_students,
+            attributes: None,
+        }
+    }
+}
+
+/// Builds a [`SubModule`] using the cached submodule if possible,
+/// as only these contain the links needed for lazy loading
+fn event_to_submodule(stine: &mut Stine, event: &StudentEvent, course_id: &str,
+                      appointments: &HashMap<String, Vec<Appointment>>) -> SubModule {
+    if let Ok(cached) = stine.get_submodule_by_id(course_id.to_string(), false, LazyLevel::FullLazy) {
+        return cached.clone();
+    }
+
+    let course_number = event.course_number.clone().unwrap_or_default();
+    let name = format!("{} {}", course_number, event.course_name.as_deref().unwrap_or_default());
+
+    SubModule {
+        id: course_id.to_string(),
+        course_number,
+        name: name.trim().to_string(),
+        info: LazyLoaded {
+            status: Lazy::Loaded(CourseInfo::from(event)),
+            link: String::new(),
+        },
+        appointments: LazyLoaded {
+            status: Lazy::Loaded(appointments.get(course_id).cloned()),
+            link: String::new(),
+        },
+        groups: LazyLoaded {
+            status: Lazy::Loaded(None),
+            link: String::new(),
+        },
+    }
+}
+
+/// Returns the registered courses and whether they were accepted
+fn get_events_mobile(stine: &mut Stine) -> Result<Vec<(SubModule, bool)>, anyhow::Error> {
+    let events = stine.get_student_events()?;
+
+    // fetch the appointments of all semesters with a single request
+    let ranges: Vec<RangeInclusive<NaiveDate>> = events.iter()
+        .filter_map(|e| e.semester_name.as_ref().map(Semester::date_range))
+        .collect();
+    let appointments = match (ranges.iter().map(|r| *r.start()).min(), ranges.iter().map(|r| *r.end()).max()) {
+        (Some(start), Some(end)) => stine.get_appointments_mobile(start..=end)?,
+        _ => HashMap::new(),
+    };
+
+    Ok(events.iter()
+        .filter_map(|event| event.course_id.as_ref().map(|course_id| (
+            event_to_submodule(stine, event, course_id, &appointments),
+            event.accepted_status.unwrap_or_default(),
+        )))
+        .collect())
+}
+
+fn get_my_registrations_mobile(stine: &mut Stine) -> Result<MyRegistrations, anyhow::Error> {
+    let mut registrations = MyRegistrations {
+        pending_submodules: vec![],
+        accepted_submodules: vec![],
+        rejected_submodules: vec![],
+        accepted_modules: vec![],
+    };
+
+    for (submodule, accepted) in get_events_mobile(stine)? {
+        if accepted {
+            registrations.accepted_submodules.push(submodule);
+        } else {
+            registrations.pending_submodules.push(submodule);
+        }
+    }
+
+    Ok(registrations)
+}
+
+fn exam_to_course_result(exam: &StudentExam) -> CourseResult {
+    let (number, name) = exam.context.split_once(' ')
+        .unwrap_or((exam.context.as_str(), exam.exam_name.as_str()));
+
+    CourseResult {
+        number: number.to_string(),
+        name: name.trim().to_string(),
+        final_grade: match exam.grade {
+            Some(ExamGrade::Numeric(grade)) => Some(grade),
+            _ => None,
+        },
+        credits: None,
+        status: exam.status.as_ref().map_or_else(|| exam.raw.status.clone(), ToString::to_string),
+        grade_stats: None,
+    }
+}
+
+/// Groups the exams by semester. Only the latest attempt of every exam in a semester is used,
+/// see [`ExamHistory`] for the order of the attempts
+fn exams_to_semester_results(exams: Vec<StudentExam>, semesters: &[Semester]) -> Vec<SemesterResult> {
+    let exams = exams.into_iter()
+        .filter(|exam| exam.semester.as_ref().is_some_and(|s| semesters.is_empty() || semesters.contains(s)))
+        .collect();
+
+    let mut latest: Vec<StudentExam> = Vec::new();
+    for history in ExamHistory::from_exams(exams) {
+        let mut latest_of_history: Vec<StudentExam> = Vec::new();
+        // attempts are sorted, so later attempts of the same semester replace earlier ones
+        for attempt in history.attempts {
+            match latest_of_history.iter_mut().find(|e| e.semester == attempt.exam.semester) {
+                Some(existing) => *existing = attempt.exam,
+                None => latest_of_history.push(attempt.exam),
+            }
+        }
+        latest.extend(latest_of_history);
+    }
+
+    let mut results: Vec<SemesterResult> = Vec::new();
+    for exam in latest {
+        let semester = exam.semester.clone().unwrap();
+        let course_result = exam_to_course_result(&exam);
+
+        match results.iter_mut().find(|r| r.semester == semester) {
+            Some(result) => result.courses.push(course_result),
+            None => results.push(SemesterResult {
+                semester,
+                courses: vec![course_result],
+                semester_gpa: Err("Not available using the mobile api".to_string()),
+                semester_credits: String::new(),
+            }),
+        }
+    }
+
+    results
+}
+
+impl Backend for MobileBackend {
+    fn get_my_registrations(&self, stine: &mut Stine, lazy: LazyLevel)
+                            -> Result<MyRegistrations, anyhow::Error> {
+        let mobile = get_my_registrations_mobile(stine);
+        or_html(mobile, || HtmlBackend.get_my_registrations(stine, lazy))
+    }
+
+    fn get_semester_results(&self, stine: &Stine, semesters: Vec<Semester>, lazy: LazyLevel)
+                            -> Result<Vec<SemesterResult>, anyhow::Error> {
+        let mobile = stine.get_exams_mobile()
+            .map(|exams| exams_to_semester_results(exams.exams, &semesters));
+        or_html(mobile, move || HtmlBackend.get_semester_results(stine, semesters, lazy))
+    }
+
+    fn get_all_semester_results(&self, stine: &Stine, lazy: LazyLevel)
+                                -> Result<Vec<SemesterResult>, anyhow::Error> {
+        let mobile = stine.get_exams_mobile()
+            .map(|exams| exams_to_semester_results(exams.exams, &[]));
+        or_html(mobile, || HtmlBackend.get_all_semester_results(stine, lazy))
+    }
+
+    fn get_events(&self, stine: &mut Stine, lazy: LazyLevel) -> Result<Vec<SubModule>, anyhow::Error> {
+        let mobile = get_events_mobile(stine)
+            .map(|events| events.into_iter().map(|(submodule, _)| submodule).collect());
+        or_html(mobile, || HtmlBackend.get_events(stine, lazy))
+    }
+
+    fn get_appointments(&self, stine: &mut Stine, range: RangeInclusive<NaiveDate>)
+                        -> Result<HashMap<String, Vec<Appointment>>, anyhow::Error> {
+        let mobile = stine.get_appointments_mobile(range.clone());
+        or_html(mobile, || HtmlBackend.get_appointments(stine, range))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mobile::backend::exams_to_semester_results;
+    use crate::mobile::StudentExamRaw;
+    use crate::Semester;
+
+    fn exam(id: &str, context: &str, grade: &str, due_date: &str, semester: &str) -> StudentExamRaw {
+        StudentExamRaw {
+            exam_id: id.to_string(),
+            exam_name: "Klausur".to_string(),
+            context: context.to_string(),
+            context_type: "course".to_string(),
+            subject: String::new(),
+            begin_date: String::new(),
+            due_date: due_date.to_string(),
+            time_from: String::new(),
+            time_to: String::new(),
+            grade: grade.to_string(),
+            grade_description: String::new(),
+            instructor_string: String::new(),
+            status: "bestanden".to_string(),
+            status_system: "1".to_string(),
+            semester_id: String::new(),
+            semester_name: semester.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_exams_to_semester_results() {
+        let exams = vec![
+            exam("1", "64-074 Berechenbarkeit", "5,0", "15.02.2024", "WiSe 23/24"),
+            exam("1", "64-074 Berechenbarkeit", "2,3", "28.03.2024", "WiSe 23/24"),
+            exam("2", "64-060 Statistik", "1,0", "20.07.2024", "SoSe 24"),
+        ].into_iter().map(Into::into).collect();
+
+        let results = exams_to_semester_results(exams, &[]);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].semester, Semester::new_winter(23, 24));
+        assert_eq!(results[0].courses.len(), 1);
+        assert_eq!(results[0].courses[0].number, "64-074");
+        assert_eq!(results[0].courses[0].name, "Berechenbarkeit");
+        assert_eq!(results[0].courses[0].final_grade, Some(2.3));
+
+        // the order of the api must not matter, exams without time are compared by date
+        let reversed = vec![
+            exam("1", "64-074 Berechenbarkeit", "2,3", "28.03.2024", "WiSe 23/24"),
+            exam("1", "64-074 Berechenbarkeit", "5,0", "15.02.2024", "WiSe 23/24"),
+        ].into_iter().map(Into::into).collect();
+        let results = exams_to_semester_results(reversed, &[]);
+        assert_eq!(results[0].courses.len(), 1);
+        assert_eq!(results[0].courses[0].final_grade, Some(2.3));
+
+        // a retake in a later semester is shown in both semesters
+        let retake = vec![
+            exam("1", "64-074 Berechenbarkeit", "2,3", "20.07.2024", "SoSe 24"),
+            exam("1", "64-074 Berechenbarkeit", "5,0", "15.02.2024", "WiSe 23/24"),
+        ].into_iter().map(Into::into).collect();
+        let results = exams_to_semester_results(retake, &[]);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results.iter().map(|r| r.courses[0].final_grade).collect::<Vec<_>>(), [Some(5.0), Some(2.3)]);
+
+        let results = exams_to_semester_results(
+            vec![exam("2", "64-060 Statistik", "1,0", "20.07.2024", "SoSe 24").into()],
+            &[Semester::new_winter(23, 24)]);
+        assert!(results.is_empty());
+    }
+}
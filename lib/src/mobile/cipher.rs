@@ -1,4 +1,5 @@
 use std::num::NonZeroU32;
+use std::string::FromUtf8Error;
 
 use base64::{Engine, engine::general_purpose};
 use cipher::{AsyncStreamCipher, KeyIvInit};
@@ -7,6 +8,30 @@ use md5::{Digest, Md5};
 use rand::Rng;
 use ring::pbkdf2;
 use ring::pbkdf2::PBKDF2_HMAC_SHA1;
+use serde::Serialize;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum DecryptError {
+    #[error("Invalid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[error("Encrypted input too short: expected at least 48 bytes, got {0}")]
+    TooShort(usize),
+    #[error("Decrypted data is not valid utf-8: {0}")]
+    Utf8(#[from] FromUtf8Error),
+    #[error("Decrypted data is missing the {0}")]
+    MissingField(&'static str),
+    #[error("MD5 checksum mismatch: expected {expected}, calculated {calculated}")]
+    ChecksumMismatch { expected: String, calculated: String },
+}
+
+/// Content of the encrypted `-A` argument of the mobile endpoints. See [`encrypt_arguments`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DecryptedArguments {
+    pub prg_name: String,
+    pub session_id: String,
+    pub arguments: Vec<String>,
+}
 
 fn create_secret_key(str: &[u8], salt: &[u8]) -> [u8; 16] {
     let mut store = [0u8; 16];
@@ -22,12 +47,12 @@ fn base64_encode(input: &[u8]) -> String {
         .replace('/', "~")
 }
 
-fn base64_decode(input: String) -> Vec<u8> {
-    let input = input.replace("\n", "")
+fn base64_decode(input: &str) -> Result<Vec<u8>, base64::DecodeError> {
+    let input = input.replace('\n', "")
         .replace('-', "+")
         .replace('_', "=")
         .replace('~', "/");
-    general_purpose::STANDARD.decode(input).unwrap()
+    general_purpose::STANDARD.decode(input)
 }
 
 type Aes128CfbEnc = cfb_mode::Encryptor<aes::Aes128>;
@@ -60,8 +85,12 @@ fn encrypt(input: String) -> String {
     base64_encode(&output)
 }
 
-fn decrypt(input: String) -> String {
-    let input_bytes = base64_decode(input);
+fn decrypt(input: &str) -> Result<String, DecryptError> {
+    let input_bytes = base64_decode(input)?;
+    // iv + salt + obfuscation key
+    if input_bytes.len() < 48 {
+        return Err(DecryptError::TooShort(input_bytes.len()));
+    }
 
     let iv = input_bytes[..16].to_vec();
     let salt = input_bytes[16..32].to_vec();
@@ -72,7 +101,7 @@ fn decrypt(input: String) -> String {
     Aes128CfbDec::new(&key.into(), GenericArray::from_slice(iv.as_slice()))
         .decrypt(&mut decrypted_data);
     let decrypted_data = decrypted_data[16..].to_vec();
-    String::from_utf8(decrypted_data).unwrap()
+    Ok(String::from_utf8(decrypted_data)?)
 }
 
 pub fn encrypt_arguments(prg_name: String, session_id: String, args: Vec<&str>) -> String {
@@ -82,10 +111,51 @@ pub fn encrypt_arguments(prg_name: String, session_id: String, args: Vec<&str>)
     encrypt(hash + "," + args)
 }
 
+/// Percent-encodes free text, like the content of a message, for use as a single argument.
+/// Commas would otherwise split the text into several arguments
+pub fn encode_text_argument(text: &str) -> String {
+    text.replace('%', "%25").replace(',', "%2C")
+}
+
+/// Decrypts and verifies arguments encrypted with [`encrypt_arguments`],
+/// e.g. captured from the traffic of the mobile app.
+/// # Arguments
+/// * input - the encrypted arguments, with or without the leading `-A`
+pub fn decrypt_arguments(input: &str) -> Result<DecryptedArguments, DecryptError> {
+    let input = input.trim();
+    let decrypted = decrypt(input.strip_prefix("-A").unwrap_or(input))?;
+
+    let (hash, args) = decrypted.split_once(',')
+        .ok_or(DecryptError::MissingField("checksum"))?;
+
+    let calculated = hex::encode(Md5::digest(args.as_bytes())).to_uppercase();
+    if !hash.eq_ignore_ascii_case(&calculated) {
+        return Err(DecryptError::ChecksumMismatch { expected: hash.to_string(), calculated });
+    }
+
+    let mut args = args.split(',').map(ToString::to_string);
+    Ok(DecryptedArguments {
+        prg_name: args.next().ok_or(DecryptError::MissingField("PRGNAME"))?,
+        session_id: args.next().ok_or(DecryptError::MissingField("session id"))?,
+        arguments: args.collect(),
+    })
+}
+
 
 #[cfg(test)]
 mod tests_mobile_cipher {
-    use crate::mobile::cipher::{base64_encode, create_secret_key, decrypt, encrypt, encrypt_arguments};
+    use crate::mobile::cipher::{base64_encode, create_secret_key, decrypt, decrypt_arguments, DecryptError,
+                                encode_text_argument, encrypt, encrypt_arguments};
+
+    #[test]
+    fn test_encode_text_argument() {
+        assert_eq!(encode_text_argument("Hallo, danke!"), "Hallo%2C danke!");
+        assert_eq!(encode_text_argument("100%, ok"), "100%25%2C ok");
+
+        let args = decrypt_arguments(&encrypt_arguments("REPLYMESSAGE".to_string(), "1".to_string(),
+                                                        vec!["000000", "42", &encode_text_argument("Ja, gerne")])).unwrap();
+        assert_eq!(args.arguments, ["000000", "42", "Ja%2C gerne"]);
+    }
 
     #[test]
     fn test_key_gen() {
@@ -102,7 +172,7 @@ mod tests_mobile_cipher {
     #[test]
     fn test_encryption() {
         let enc = encrypt("test".to_string());
-        let dec = decrypt(enc);
+        let dec = decrypt(&enc).unwrap();
         assert_eq!(dec, "test")
     }
 
@@ -110,13 +180,34 @@ mod tests_mobile_cipher {
     fn test_arg_encryption() {
         let enc = encrypt_arguments(
             "GETEXAMS".to_string(), "322587234897118".to_string(), vec!["000000", "STD"]);
-        let dec = decrypt(enc);
+        let dec = decrypt(&enc).unwrap();
         assert_eq!(dec.split(',').skip(1).collect::<Vec<&str>>(), vec!["GETEXAMS", "322587234897118", "000000", "STD"])
     }
 
+    #[test]
+    fn test_arg_decryption() {
+        let enc = encrypt_arguments(
+            "GETEXAMS".to_string(), "322587234897118".to_string(), vec!["000000", "STD"]);
+
+        let dec = decrypt_arguments(&format!("-A{enc}")).unwrap();
+        assert_eq!(dec.prg_name, "GETEXAMS");
+        assert_eq!(dec.session_id, "322587234897118");
+        assert_eq!(dec.arguments, vec!["000000", "STD"]);
+    }
+
+    #[test]
+    fn test_arg_decryption_invalid() {
+        assert!(matches!(decrypt_arguments("dGVzdA__"), Err(DecryptError::TooShort(4))));
+        assert!(matches!(decrypt_arguments("not base64!"), Err(DecryptError::Base64(_))));
+
+        // valid encryption, but no checksum
+        let enc = encrypt("GETEXAMS,322587234897118,000000".to_string());
+        assert!(matches!(decrypt_arguments(&enc), Err(DecryptError::ChecksumMismatch { .. })));
+    }
+
     #[test]
     fn test_decryption() {
-        let dec = decrypt("D29eG5fjMQg2-pLsosNNJXtyUUecTow~L8L7GXBXXjbk-iG3c12j3PlHWCyvTs81hS241A__".to_string());
+        let dec = decrypt("D29eG5fjMQg2-pLsosNNJXtyUUecTow~L8L7GXBXXjbk-iG3c12j3PlHWCyvTs81hS241A__").unwrap();
         assert_eq!(dec, "test")
     }
 }
\ No newline at end of file
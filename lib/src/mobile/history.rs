@@ -0,0 +1,131 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::mobile::{ExamGrade, ExamStatus, StudentExam};
+
+/// A single attempt of an exam
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ExamAttempt {
+    /// Number of the attempt, starting at 1.
+    /// Matches the `attempt` of [`crate::Stine::get_grade_stats_for_exam`]
+    pub attempt: u8,
+    pub exam: StudentExam,
+}
+
+impl ExamAttempt {
+    /// Date of the exam, `None` if it was not scheduled yet
+    pub fn date(&self) -> Option<NaiveDate> {
+        self.exam.due_date.or(self.exam.begin_date)
+    }
+
+    /// Failed exams have the status failed, or are graded with failed or 5.0
+    pub fn is_failed(&self) -> bool {
+        matches!(self.exam.status, Some(ExamStatus::Failed))
+            || match self.exam.grade {
+            Some(ExamGrade::Failed) => true,
+            Some(ExamGrade::Numeric(grade)) => grade >= 5.0,
+            _ => false,
+        }
+    }
+}
+
+/// All attempts of an exam in chronological order
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ExamHistory {
+    /// Module or course of the exam. See [`StudentExam::context`]
+    pub context: String,
+    pub exam_name: String,
+    pub attempts: Vec<ExamAttempt>,
+}
+
+impl ExamHistory {
+    /// Groups exams with the same context and name into histories.
+    /// Attempts are sorted by date, exams without a date are considered the latest attempts
+    pub fn from_exams(exams: Vec<StudentExam>) -> Vec<ExamHistory> {
+        let mut histories: Vec<ExamHistory> = Vec::new();
+
+        for exam in exams {
+            let attempt = ExamAttempt { attempt: 0, exam };
+            match histories.iter_mut()
+                .find(|h| h.context == attempt.exam.context && h.exam_name == attempt.exam.exam_name) {
+                Some(history) => history.attempts.push(attempt),
+                None => histories.push(ExamHistory {
+                    context: attempt.exam.context.clone(),
+                    exam_name: attempt.exam.exam_name.clone(),
+                    attempts: vec![attempt],
+                }),
+            }
+        }
+
+        for history in &mut histories {
+            history.attempts.sort_by_key(|a| (a.date().is_none(), a.date()));
+            for (i, attempt) in history.attempts.iter_mut().enumerate() {
+                attempt.attempt = u8::try_from(i + 1).unwrap_or(u8::MAX);
+            }
+        }
+
+        histories
+    }
+
+    pub fn latest(&self) -> Option<&ExamAttempt> {
+        self.attempts.last()
+    }
+
+    /// Checks if a failed attempt was followed by another attempt
+    pub fn is_retake(&self) -> bool {
+        self.attempts.iter().rev().skip(1).any(ExamAttempt::is_failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::mobile::{ExamHistory, StudentExamRaw};
+
+    fn exam(name: &str, context: &str, grade: &str, due_date: &str) -> StudentExamRaw {
+        StudentExamRaw {
+            exam_id: "108751472457".to_string(),
+            exam_name: name.to_string(),
+            context: context.to_string(),
+            context_type: "course".to_string(),
+            subject: String::new(),
+            begin_date: String::new(),
+            due_date: due_date.to_string(),
+            time_from: String::new(),
+            time_to: String::new(),
+            grade: grade.to_string(),
+            grade_description: String::new(),
+            instructor_string: String::new(),
+            status: String::new(),
+            status_system: String::new(),
+            semester_id: String::new(),
+            semester_name: "WiSe 23/24".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_exam_history() {
+        let exams = vec![
+            exam("Klausur", "64-074 Berechenbarkeit", "", ""),
+            exam("Klausur", "64-074 Berechenbarkeit", "2,3", "28.03.2024"),
+            exam("Klausur", "64-074 Berechenbarkeit", "5,0", "15.02.2024"),
+            exam("Online-Tests", "64-074 Berechenbarkeit", "b", "01.02.2024"),
+        ].into_iter().map(Into::into).collect();
+
+        let histories = ExamHistory::from_exams(exams);
+        assert_eq!(histories.len(), 2);
+
+        let klausur = &histories[0];
+        assert_eq!(klausur.attempts.len(), 3);
+        assert_eq!(klausur.attempts.iter().map(|a| a.attempt).collect::<Vec<u8>>(), vec![1, 2, 3]);
+        assert_eq!(klausur.attempts[0].date(), NaiveDate::from_ymd_opt(2024, 2, 15));
+        assert!(klausur.attempts[0].is_failed());
+        assert_eq!(klausur.latest().unwrap().date(), None);
+        assert!(klausur.is_retake());
+
+        let online_tests = &histories[1];
+        assert_eq!(online_tests.attempts.len(), 1);
+        assert!(!online_tests.is_retake());
+    }
+}
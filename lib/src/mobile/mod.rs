@@ -1,15 +1,25 @@
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
 use std::str::FromStr;
+use std::time::Instant;
 
+use anyhow::anyhow;
+use chrono::{DateTime, NaiveDate, Utc};
 use reqwest::blocking::Response;
 use reqwest::header::{ACCEPT, ACCEPT_ENCODING, CONNECTION, CONTENT_LENGTH, COOKIE, HeaderMap, HeaderValue, HOST, USER_AGENT};
 use serde::{Deserialize, Serialize};
 
-use crate::{EventType, Semester, Stine, stine::API_URL};
+use crate::{Appointment, CourseInfo, EventType, Semester, Stine, stine::API_URL};
+use crate::stine::log_request_duration;
 
+pub use backend::MobileBackend;
+pub use history::{ExamAttempt, ExamHistory};
+
+mod backend;
 pub mod cipher;
+mod history;
 mod parse;
-//TODO: implement: appointments and eventinfo, and messages
 
 // Possible types / prg_names / programm names?:
 // GETPERSONTYPE
@@ -25,7 +35,7 @@ mod parse;
 // GETEVENTINFO
 // GETEVENTS
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 /// StudentEvent
 /// Contains general info about an event
 /// Endpoint: GETEVENTS
@@ -39,6 +49,7 @@ pub struct StudentEvent {
     pub semester_id: Option<String>,
     pub semester_name: Option<Semester>,
     pub credits: Option<f32>,
+    pub hours_per_week: Option<i32>,
     pub small_groups: Option<i32>,
     pub language: Option<String>,
     pub faculty_name: Option<String>,
@@ -50,14 +61,17 @@ pub struct StudentEvent {
     pub accepted_status: Option<bool>,
     pub material_present: Option<bool>,
     pub info_present: Option<bool>,
+
+    /// Tags without a corresponding field. Mapped by tag name
+    pub extra: HashMap<String, String>,
 }
 
-// TODO: not every type should be string
-/// **Will change**
+/// Unprocessed [`StudentExam`] as returned by the GETEXAMS endpoint. Every field is a string.
+/// Mostly useful for debugging
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 #[serde(rename = "mgns1:studentExam")]
 #[serde(rename_all = "camelCase")]
-pub struct StudentExam {
+pub struct StudentExamRaw {
     #[serde(rename = "examID")]
     pub exam_id: String,
     pub exam_name: String,
@@ -86,12 +100,132 @@ pub struct StudentExam {
     // other: HashMap<String, Value>,
 }
 
+/// Exam of the GETEXAMS endpoint.
+/// (De)serializes to the same format as [`StudentExamRaw`]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(from = "StudentExamRaw", into = "StudentExamRaw")]
+pub struct StudentExam {
+    pub exam_id: String,
+    pub exam_name: String,
+    /// Module or course of the exam, e.g. "24-300.10 Ringvorlesung zur Klimakrise"
+    pub context: String,
+    pub context_type: String,
+    pub subject: Option<String>,
+    pub begin_date: Option<NaiveDate>,
+    pub due_date: Option<NaiveDate>,
+    /// `time_from` on the `due_date`
+    pub datetime_from: Option<DateTime<Utc>>,
+    /// `time_to` on the `due_date`
+    pub datetime_to: Option<DateTime<Utc>>,
+    pub grade: Option<ExamGrade>,
+    pub grade_description: Option<String>,
+
+    /// the instructor(s)
+    pub instructor_string: Option<String>,
+    pub status: Option<ExamStatus>,
+    pub status_system: Option<i32>,
+
+    pub semester_id: Option<String>,
+    pub semester: Option<Semester>,
+
+    /// The unprocessed strings
+    pub raw: StudentExamRaw,
+}
+
+impl From<StudentExamRaw> for StudentExam {
+    fn from(raw: StudentExamRaw) -> Self {
+        parse::parse_student_exam(raw)
+    }
+}
+
+impl From<StudentExam> for StudentExamRaw {
+    fn from(exam: StudentExam) -> Self {
+        exam.raw
+    }
+}
+
 impl Display for StudentExam {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self)
     }
 }
 
+/// Grade of a [`StudentExam`]. Some exams are only graded with passed or failed
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ExamGrade {
+    Numeric(f32),
+    Passed,
+    Failed,
+}
+
+impl FromStr for ExamGrade {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "b" | "bestanden" | "p" | "passed" => Ok(Self::Passed),
+            "nb" | "nicht bestanden" | "f" | "failed" | "not passed" => Ok(Self::Failed),
+            grade => Ok(Self::Numeric(grade.replace(',', ".").parse()
+                .map_err(|_| anyhow!("Invalid exam grade: {s}"))?)),
+        }
+    }
+}
+
+impl Display for ExamGrade {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Numeric(grade) => write!(f, "{grade:.1}"),
+            Self::Passed => write!(f, "passed"),
+            Self::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// Status of a [`StudentExam`]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum ExamStatus {
+    Passed,
+    Failed,
+    /// The result was not published yet
+    NotPublished,
+    Registered,
+    Excused,
+    Ill,
+
+    Unknown(String),
+}
+
+impl FromStr for ExamStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "bestanden" | "passed" => Ok(Self::Passed),
+            "nicht bestanden" | "failed" | "not passed" => Ok(Self::Failed),
+            "noch nicht veröffentlicht" | "not yet published" | "not published yet" => Ok(Self::NotPublished),
+            "angemeldet" | "registered" => Ok(Self::Registered),
+            "entschuldigt" | "excused" => Ok(Self::Excused),
+            "krank" | "ill" => Ok(Self::Ill),
+
+            _ => Ok(Self::Unknown(s.trim().to_string())),
+        }
+    }
+}
+
+impl Display for ExamStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Passed => write!(f, "passed"),
+            Self::Failed => write!(f, "failed"),
+            Self::NotPublished => write!(f, "not yet published"),
+            Self::Registered => write!(f, "registered"),
+            Self::Excused => write!(f, "excused"),
+            Self::Ill => write!(f, "ill"),
+            Self::Unknown(status) => write!(f, "{status}"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename = "mgns1:Message")]
 pub struct StudentExams {
@@ -99,6 +233,50 @@ pub struct StudentExams {
     pub exams: Vec<StudentExam>,
 }
 
+/// Message of your STINE inbox, e.g. an announcement sent by an instructor
+/// Endpoint: GETMESSAGES
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Message {
+    pub message_id: String,
+    pub subject: Option<String>,
+    /// Name of the sender, mostly the instructor or the course
+    pub sender: Option<String>,
+    pub datetime: Option<DateTime<Utc>>,
+    pub content: Option<String>,
+    pub is_read: Option<bool>,
+    /// Course the message was sent to, if it was sent to a course
+    pub course_id: Option<String>,
+}
+
+impl Display for Message {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}",
+               self.sender.as_deref().unwrap_or("-"),
+               self.subject.as_deref().unwrap_or("-"))
+    }
+}
+
+/// Course material, like lecture slides or exercise sheets
+/// Endpoint: GETMATERIAL
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Material {
+    pub material_id: String,
+    /// File name of the material
+    pub name: String,
+    /// Size in bytes
+    pub size: Option<u64>,
+    /// Last time the material was uploaded or changed
+    pub datetime: Option<DateTime<Utc>>,
+    pub course_id: String,
+    pub course_name: Option<String>,
+}
+
+impl Display for Material {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum ActorType {
     Applicant,
@@ -154,7 +332,10 @@ impl Stine {
 
 
         log::debug!("GET to: {url}, \nArguments: {args}");
-        self.client.get(url).headers(headers).send()
+        let start = Instant::now();
+        let response = self.client.get(url).headers(headers).send();
+        log_request_duration("GET", prg_name, start);
+        response
     }
 
     pub fn get_exams_mobile(&self) -> Result<StudentExams, anyhow::Error> {
@@ -163,6 +344,11 @@ impl Stine {
         Ok(parse::parse_get_exams(xml_response.text()?)?)
     }
 
+    /// Returns all attempts of your exams, see [`ExamHistory`]
+    pub fn get_exam_history(&self) -> Result<Vec<ExamHistory>, anyhow::Error> {
+        Ok(ExamHistory::from_exams(self.get_exams_mobile()?.exams))
+    }
+
     pub fn get_actor_type(&self) -> Result<ActorType, anyhow::Error> {
         // "1" for short results, e.g. "STD", "0" for results like "student",
         // however ActorType::FromString only works for the short version
@@ -174,4 +360,76 @@ impl Stine {
         let xml_response = self.get_mobile("GETEVENTS", vec!["000000"])?;
         parse::parse_student_events(xml_response.text()?)
     }
+
+    /// Returns the appointments of all your courses in the date range.
+    /// The appointments are mapped by the course id, see [`StudentEvent::course_id`].
+    /// Requires just one request, in contrast to loading the appointments of every [`crate::SubModule`]
+    pub fn get_appointments_mobile(&self, range: RangeInclusive<NaiveDate>)
+                                   -> Result<HashMap<String, Vec<Appointment>>, anyhow::Error> {
+        let from = range.start().format("%d.%m.%Y").to_string();
+        let to = range.end().format("%d.%m.%Y").to_string();
+
+        let xml_response = self.get_mobile("GETAPPOINTMENTS",
+                                           vec!["000000", from.as_str(), to.as_str()])?;
+        parse::parse_appointments(xml_response.text()?)
+    }
+
+    /// Returns the [`CourseInfo`] of a course
+    /// # Arguments
+    /// * course_id - id of the course, e.g. [`StudentEvent::course_id`]
+    pub fn get_event_info_mobile(&self, course_id: &str) -> Result<CourseInfo, anyhow::Error> {
+        let xml_response = self.get_mobile("GETEVENTINFO", vec!["000000", course_id])?;
+        parse::parse_event_info(xml_response.text()?)
+    }
+
+    /// Returns the materials of a course
+    /// # Arguments
+    /// * course_id - id of the course, e.g. [`StudentEvent::course_id`]
+    pub fn get_materials(&self, course_id: &str) -> Result<Vec<Material>, anyhow::Error> {
+        let xml_response = self.get_mobile("GETMATERIAL", vec!["000000", course_id])?;
+        parse::parse_materials(xml_response.text()?, course_id)
+    }
+
+    /// Downloads the file of the [`Material`]
+    pub fn download_material(&self, material: &Material) -> Result<Vec<u8>, anyhow::Error> {
+        let response = self.get_mobile("GETEVENTDOWNLOAD",
+                                       vec!["000000", material.material_id.as_str()])?
+            .error_for_status()?;
+        Ok(response.bytes()?.to_vec())
+    }
+
+    /// Returns all messages of your STINE inbox
+    pub fn get_messages(&self) -> Result<Vec<Message>, anyhow::Error> {
+        let xml_response = self.get_mobile("GETMESSAGES", vec!["000000"])?;
+        parse::parse_messages(xml_response.text()?)
+    }
+
+    /// Marks the message as read or unread
+    pub fn set_message_status(&self, message_id: &str, read: bool) -> Result<(), anyhow::Error> {
+        let status = if read { "1" } else { "0" };
+        let xml_response = self.get_mobile("SETMESSAGESTATUS",
+                                           vec!["000000", message_id, status])?;
+        parse::parse_action_response(xml_response.text()?)
+    }
+
+    /// Marks the message as read. Shortcut for [`Stine::set_message_status`]
+    pub fn mark_message_read(&self, message_id: &str) -> Result<(), anyhow::Error> {
+        self.set_message_status(message_id, true)
+    }
+
+    /// Replies to the message with the given id.
+    /// `content` is percent-encoded, as commas separate the arguments of the mobile endpoints
+    pub fn reply_message(&self, message_id: &str, content: &str) -> Result<(), anyhow::Error> {
+        let content = cipher::encode_text_argument(content);
+        let xml_response = self.get_mobile("REPLYMESSAGE",
+                                           vec!["000000", message_id, &content])?;
+        parse::parse_action_response(xml_response.text()?)
+    }
+
+    /// Deletes the message with the given id from your inbox
+    pub fn delete_message(&self, message_id: &str) -> Result<(), anyhow::Error> {
+        let xml_response = self.get_mobile("DELETEMESSAGE",
+                                           vec!["000000", message_id])?;
+        parse::parse_action_response(xml_response.text()?)
+    }
 }
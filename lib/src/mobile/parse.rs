@@ -2,13 +2,17 @@ use std::collections::HashMap;
 use std::str::FromStr;
 
 use anyhow::anyhow;
+use chrono::NaiveDateTime;
 
-use crate::{EventType, Semester};
-use crate::mobile::{ActorType, StudentEvent, StudentExams};
+use crate::{Appointment, CourseInfo, EventType, Semester};
+use crate::mobile::{ActorType, ExamGrade, ExamStatus, Material, Message, StudentEvent, StudentExam, StudentExamRaw, StudentExams};
+use crate::parse::date::{parse_dmy_long_date, parse_time, stine_naive_to_utc};
+use crate::parse::parse_instructors;
 
 fn get_flat_attrs(root: roxmltree::Node) -> HashMap<String, String> {
     let mut flat_attrs: HashMap<String, String> = HashMap::new();
-    for child in root.descendants() {
+    // only leaf elements contain values, the others contain just whitespace
+    for child in root.descendants().filter(|n| n.is_element() && !n.children().any(|c| c.is_element())) {
         // dbg!(child.tag_name().name());
         // dbg!(child.first_child());
         if let Some(text_child) = child.first_child() {
@@ -43,6 +47,7 @@ pub(crate) fn parse_student_events(input: String) -> Result<Vec<StudentEvent>, a
             semester_id: flat_attrs.remove("semesterID"),
             semester_name: flat_attrs.remove("semesterName").and_then(|n| Semester::from_str(&n).ok()),
             credits: flat_attrs.remove("creditPoints").and_then(|c| f32::from_str(&c).ok()),
+            hours_per_week: flat_attrs.remove("hoursPerWeek").and_then(|c| i32::from_str(&c).ok()),
             small_groups: flat_attrs.remove("smallGroups").and_then(|c| i32::from_str(&c).ok()),
             language: flat_attrs.remove("courseLanguage"),
             faculty_name: flat_attrs.remove("facultyName"),
@@ -54,6 +59,7 @@ pub(crate) fn parse_student_events(input: String) -> Result<Vec<StudentEvent>, a
             accepted_status: flat_attrs.remove("acceptedStatus").and_then(|c| bool_from_string(c.as_str())),
             material_present: flat_attrs.remove("materialPresent").and_then(|c| bool_from_string(c.as_str())),
             info_present: flat_attrs.remove("infoPresent").and_then(|c| bool_from_string(c.as_str())),
+            extra: flat_attrs,
         });
     }
 
@@ -61,6 +67,129 @@ pub(crate) fn parse_student_events(input: String) -> Result<Vec<StudentEvent>, a
     Ok(events)
 }
 
+pub fn parse_messages(input: String) -> Result<Vec<Message>, anyhow::Error> {
+    let doc = roxmltree::Document::parse(&input)?;
+    let mut messages = vec![];
+    for message in doc.descendants().filter(|n| n.has_tag_name("message")) {
+        let mut flat_attrs = get_flat_attrs(message);
+
+        let message_id = flat_attrs.remove("messageID")
+            .ok_or_else(|| anyhow!("Failed parsing message XML: Missing messageID"))?;
+
+        let date = flat_attrs.remove("date").and_then(|d| parse_dmy_long_date(&d).ok());
+        let time = flat_attrs.remove("time").and_then(|t| parse_time(&t).ok());
+        let datetime = date.map(|date| stine_naive_to_utc(
+            NaiveDateTime::new(date, time.unwrap_or_default())));
+
+        messages.push(Message {
+            message_id,
+            subject: flat_attrs.remove("subject"),
+            sender: flat_attrs.remove("sender"),
+            datetime,
+            content: flat_attrs.remove("content"),
+            is_read: flat_attrs.remove("status").and_then(|c| bool_from_string(c.as_str())),
+            course_id: flat_attrs.remove("courseID"),
+        });
+    }
+
+    Ok(messages)
+}
+
+pub fn parse_appointments(input: String) -> Result<HashMap<String, Vec<Appointment>>, anyhow::Error> {
+    let doc = roxmltree::Document::parse(&input)?;
+    let mut appointments: HashMap<String, Vec<Appointment>> = HashMap::new();
+    for appointment in doc.descendants().filter(|n| n.has_tag_name("appointment")) {
+        let mut flat_attrs = get_flat_attrs(appointment);
+
+        let course_id = flat_attrs.remove("courseID")
+            .ok_or_else(|| anyhow!("Failed parsing appointment XML: Missing courseID"))?;
+
+        let date = flat_attrs.remove("date").and_then(|d| parse_dmy_long_date(&d).ok());
+        let to_datetime = |time: Option<String>| {
+            let time = time.and_then(|t| parse_time(&t).ok())?;
+            Some(stine_naive_to_utc(NaiveDateTime::new(date?, time)))
+        };
+
+        appointments.entry(course_id).or_default().push(Appointment {
+            from: to_datetime(flat_attrs.remove("timeFrom")),
+            to: to_datetime(flat_attrs.remove("timeTo")),
+            room: flat_attrs.remove("room").unwrap_or_default(),
+            instructors: flat_attrs.remove("instructorsString").map(parse_instructors).unwrap_or_default(),
+        });
+    }
+
+    for course_appointments in appointments.values_mut() {
+        course_appointments.sort_by_key(|a| a.from);
+    }
+
+    Ok(appointments)
+}
+
+/// Parses the event info into a [`CourseInfo`].
+/// Tags without a corresponding field are saved in `attributes`
+pub fn parse_event_info(input: String) -> Result<CourseInfo, anyhow::Error> {
+    let doc = roxmltree::Document::parse(&input)?;
+    let event_info = doc.descendants().find(|n| n.has_tag_name("eventInfo"))
+        .ok_or_else(|| anyhow!("Failed parsing eventInfo XML"))?;
+    let mut flat_attrs = get_flat_attrs(event_info);
+
+    let event_type_raw = flat_attrs.remove("eventCategory");
+
+    Ok(CourseInfo {
+        event_type: event_type_raw.as_ref().and_then(|c| EventType::from_str(c).ok()),
+        event_type_raw,
+        instructors: flat_attrs.remove("instructorsString").map(parse_instructors),
+        timetable_name: flat_attrs.remove("timetableName"),
+        hours_per_week: flat_attrs.remove("hoursPerWeek").and_then(|c| i32::from_str(&c).ok()),
+        credits: flat_attrs.remove("creditPoints"),
+        language: flat_attrs.remove("courseLanguage"),
+        min_participants: flat_attrs.remove("minStudents").and_then(|c| i32::from_str(&c).ok()),
+        max_participants: flat_attrs.remove("maxStudents").and_then(|c| i32::from_str(&c).ok()),
+        attributes: if flat_attrs.is_empty() { None } else { Some(flat_attrs) },
+    })
+}
+
+/// Parses the materials of a course.
+/// Entries without a course id are assigned to the requested `course_id`
+pub fn parse_materials(input: String, course_id: &str) -> Result<Vec<Material>, anyhow::Error> {
+    let doc = roxmltree::Document::parse(&input)?;
+    let mut materials = vec![];
+    for material in doc.descendants().filter(|n| n.has_tag_name("material")) {
+        let mut flat_attrs = get_flat_attrs(material);
+
+        let material_id = flat_attrs.remove("materialID")
+            .ok_or_else(|| anyhow!("Failed parsing material XML: Missing materialID"))?;
+        let name = flat_attrs.remove("fileName")
+            .ok_or_else(|| anyhow!("Failed parsing material XML: Missing fileName"))?;
+
+        let date = flat_attrs.remove("date").and_then(|d| parse_dmy_long_date(&d).ok());
+        let time = flat_attrs.remove("time").and_then(|t| parse_time(&t).ok());
+        let datetime = date.map(|date| stine_naive_to_utc(
+            NaiveDateTime::new(date, time.unwrap_or_default())));
+
+        materials.push(Material {
+            material_id,
+            name,
+            size: flat_attrs.remove("fileSize").and_then(|s| s.trim().parse().ok()),
+            datetime,
+            course_id: flat_attrs.remove("courseID").unwrap_or_else(|| course_id.to_string()),
+            course_name: flat_attrs.remove("courseName"),
+        });
+    }
+
+    Ok(materials)
+}
+
+/// Parses the response of endpoints which only perform an action, like DELETEMESSAGE.
+/// These respond with an (mostly) empty message, or an error element in case something went wrong
+pub fn parse_action_response(input: String) -> Result<(), anyhow::Error> {
+    let doc = roxmltree::Document::parse(&input)?;
+    if let Some(error) = doc.descendants().find(|n| n.has_tag_name("error")) {
+        return Err(anyhow!("STINE responded with an error: {}", error.text().unwrap_or_default().trim()));
+    }
+    Ok(())
+}
+
 pub fn parse_actor_type(input: String) -> Result<ActorType, anyhow::Error> {
     let doc = roxmltree::Document::parse(&input).unwrap();
     let actor = doc.descendants().find(|n| n.has_tag_name("actortype"))
@@ -73,16 +202,56 @@ pub fn parse_get_exams(xml_input: String) -> Result<StudentExams, serde_xml_rs::
     serde_xml_rs::from_str(&xml_input)
 }
 
+/// Returns None for empty tags
+fn non_empty(s: &str) -> Option<String> {
+    let s = s.replace("&nbsp;", " ").trim().to_string();
+    if s.is_empty() { None } else { Some(s) }
+}
+
+pub fn parse_student_exam(raw: StudentExamRaw) -> StudentExam {
+    let begin_date = non_empty(&raw.begin_date).and_then(|d| parse_dmy_long_date(&d).ok());
+    let due_date = non_empty(&raw.due_date).and_then(|d| parse_dmy_long_date(&d).ok());
+
+    let to_datetime = |time: &str| {
+        let time = parse_time(non_empty(time)?.as_str()).ok()?;
+        Some(stine_naive_to_utc(NaiveDateTime::new(due_date.or(begin_date)?, time)))
+    };
+
+    StudentExam {
+        exam_id: raw.exam_id.clone(),
+        exam_name: raw.exam_name.clone(),
+        context: raw.context.clone(),
+        context_type: raw.context_type.clone(),
+        subject: non_empty(&raw.subject),
+        begin_date,
+        due_date,
+        datetime_from: to_datetime(&raw.time_from),
+        datetime_to: to_datetime(&raw.time_to),
+        grade: non_empty(&raw.grade).and_then(|g| ExamGrade::from_str(&g).ok()),
+        grade_description: non_empty(&raw.grade_description),
+        instructor_string: non_empty(&raw.instructor_string),
+        status: non_empty(&raw.status).and_then(|s| ExamStatus::from_str(&s).ok()),
+        status_system: non_empty(&raw.status_system).and_then(|s| s.parse().ok()),
+        semester_id: non_empty(&raw.semester_id),
+        semester: non_empty(&raw.semester_name).and_then(|s| Semester::from_str(&s).ok()),
+        raw,
+    }
+}
+
 #[cfg(test)]
 mod tests_mobile_parser {
     use std::assert_eq;
 
-    use crate::mobile::ActorType;
-    use crate::mobile::parse::{parse_actor_type, parse_get_exams, parse_student_events};
+    use chrono::NaiveDate;
+
+    use crate::{EventType, Semester};
+    use crate::mobile::{ActorType, ExamGrade, ExamStatus, StudentEvent, StudentExam};
+    use crate::mobile::parse::{parse_action_response, parse_actor_type, parse_appointments, parse_event_info, parse_get_exams, parse_materials, parse_messages, parse_student_events};
+    use crate::parse::date::stine_naive_to_utc;
 
     #[test]
     fn test_get_exams() {
-        parse_get_exams(r#"<?xml version="1.0" encoding="UTF-8" standalone="no" ?><mgns1:Message xmlns:mgns1="http://datenlotsen.de">
+        let exams = parse_get_exams(r#"<?xml version="1.0" encoding="UTF-8" standalone="no" ?><mgns1:Message xmlns:mgns1="http://datenlotsen.de">
            <mgns1:studentExam>
                 <mgns1:examID>108751472457</mgns1:examID>
                 <mgns1:examName>Online-Tests</mgns1:examName>
@@ -120,6 +289,59 @@ mod tests_mobile_parser {
                 <mgns1:semesterName>SoSe 24</mgns1:semesterName>
           </mgns1:studentExam>
         </mgns1:Message>"#.to_string()).expect("TODO: panic message");
+
+        assert_eq!(exams.exams.len(), 2);
+
+        let passed = &exams.exams[0];
+        assert_eq!(passed.grade, Some(ExamGrade::Passed));
+        assert_eq!(passed.status, Some(ExamStatus::Passed));
+        assert_eq!(passed.status_system, Some(1));
+        assert_eq!(passed.subject, None);
+        assert_eq!(passed.due_date, None);
+        assert_eq!(passed.datetime_from, None);
+        assert_eq!(passed.semester, Some(Semester::new_winter(21, 22)));
+
+        let unpublished = &exams.exams[1];
+        assert_eq!(unpublished.grade, Some(ExamGrade::Numeric(2.3)));
+        assert_eq!(unpublished.status, Some(ExamStatus::NotPublished));
+        assert_eq!(unpublished.due_date, NaiveDate::from_ymd_opt(2024, 2, 15));
+        assert_eq!(unpublished.datetime_to, Some(stine_naive_to_utc(
+            NaiveDate::from_ymd_opt(2024, 2, 15).unwrap().and_hms_opt(13, 30, 0).unwrap())));
+        assert_eq!(unpublished.semester, Some(Semester::new_summer(24)));
+        assert_eq!(unpublished.raw.grade, "2,3");
+    }
+
+    #[test]
+    fn test_student_exam_serde_roundtrip() {
+        let exams = parse_get_exams(r#"<?xml version="1.0" encoding="UTF-8" standalone="no" ?><mgns1:Message xmlns:mgns1="http://datenlotsen.de">
+          <mgns1:studentExam>
+                <mgns1:examID>108751472457</mgns1:examID>
+                <mgns1:examName>Klausur</mgns1:examName>
+                <mgns1:context>64-074 Berechenbarkeit, Komplexität und Approximation</mgns1:context>
+                <mgns1:contextType>course</mgns1:contextType>
+                <mgns1:subject/>
+                <mgns1:beginDate/>
+                <mgns1:dueDate>15.02.2024</mgns1:dueDate>
+                <mgns1:timeFrom>12:30</mgns1:timeFrom>
+                <mgns1:timeTo>13:30</mgns1:timeTo>
+                <mgns1:grade>5,0</mgns1:grade>
+                <mgns1:gradeDescription>nicht ausreichend</mgns1:gradeDescription>
+                <mgns1:instructorString/>
+                <mgns1:status>nicht bestanden</mgns1:status>
+                <mgns1:statusSystem>2</mgns1:statusSystem>
+                <mgns1:semesterID>99999999254942</mgns1:semesterID>
+                <mgns1:semesterName>WiSe 23/24</mgns1:semesterName>
+          </mgns1:studentExam>
+        </mgns1:Message>"#.to_string()).unwrap();
+
+        // saved in the format of the raw strings
+        let json = serde_json::to_string(&exams.exams[0]).unwrap();
+        assert!(json.contains(r#""grade":"5,0""#));
+
+        let exam: StudentExam = serde_json::from_str(&json).unwrap();
+        assert_eq!(exam, exams.exams[0]);
+        assert_eq!(exam.grade, Some(ExamGrade::Numeric(5.0)));
+        assert_eq!(exam.status, Some(ExamStatus::Failed));
     }
 
     #[test]
@@ -182,9 +404,164 @@ mod tests_mobile_parser {
             <mgns1:acceptedStatus>0</mgns1:acceptedStatus>
             <mgns1:materialPresent>0</mgns1:materialPresent>
             <mgns1:infoPresent>0</mgns1:infoPresent>
+            <mgns1:examPresent>1</mgns1:examPresent>
         </mgns1:studentEvent>
-    </mgns1:Message>"#.to_string());
+    </mgns1:Message>"#.to_string()).unwrap();
+
+        assert_eq!(events.len(), 2);
+
+        assert_eq!(events[0].hours_per_week, Some(4));
+        assert_eq!(events[0].event_category, Some(EventType::Lecture));
+        assert!(events[0].extra.is_empty());
+
+        assert_eq!(events[1].hours_per_week, Some(3));
+        assert_eq!(events[1].extra.get("examPresent").map(String::as_str), Some("1"));
 
-        assert_eq!(events.unwrap().len(), 2);
+        let json = serde_json::to_string(&events).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<StudentEvent>>(&json).unwrap(), events);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_messages() {
+        let messages = parse_messages(r#"<?xml version="1.0" encoding="UTF-8" standalone="no" ?>
+        <mgns1:Message xmlns:mgns1="http://datenlotsen.de">
+          <mgns1:message>
+            <mgns1:messageID>384901236548213</mgns1:messageID>
+            <mgns1:subject>Klausureinsicht</mgns1:subject>
+            <mgns1:sender>Prof. Dr. John Pork</mgns1:sender>
+            <mgns1:date>15.02.2024</mgns1:date>
+            <mgns1:time>12:30</mgns1:time>
+            <mgns1:content>Die Klausureinsicht findet am Montag statt.</mgns1:content>
+            <mgns1:status>0</mgns1:status>
+            <mgns1:courseID>379923411595682</mgns1:courseID>
+          </mgns1:message>
+          <mgns1:message>
+            <mgns1:messageID>384901236548214</mgns1:messageID>
+            <mgns1:subject/>
+            <mgns1:sender/>
+            <mgns1:date/>
+            <mgns1:time/>
+            <mgns1:content/>
+            <mgns1:status>1</mgns1:status>
+          </mgns1:message>
+        </mgns1:Message>"#.to_string()).unwrap();
+
+        assert_eq!(messages.len(), 2);
+
+        let first = &messages[0];
+        assert_eq!(first.message_id, "384901236548213");
+        assert_eq!(first.subject.as_deref(), Some("Klausureinsicht"));
+        assert_eq!(first.is_read, Some(false));
+        assert_eq!(first.datetime, Some(stine_naive_to_utc(
+            NaiveDate::from_ymd_opt(2024, 2, 15).unwrap().and_hms_opt(12, 30, 0).unwrap())));
+
+        let second = &messages[1];
+        assert_eq!(second.subject, None);
+        assert_eq!(second.datetime, None);
+        assert_eq!(second.is_read, Some(true));
+    }
+
+    #[test]
+    fn test_action_response() {
+        parse_action_response(r#"<?xml version="1.0" encoding="UTF-8" standalone="no" ?>
+        <mgns1:Message xmlns:mgns1="http://datenlotsen.de"/>"#.to_string()).unwrap();
+
+        parse_action_response(r#"<?xml version="1.0" encoding="UTF-8" standalone="no" ?>
+        <mgns1:Message xmlns:mgns1="http://datenlotsen.de">
+          <mgns1:error>Message not found</mgns1:error>
+        </mgns1:Message>"#.to_string()).unwrap_err();
+    }
+
+    #[test]
+    fn test_materials() {
+        let materials = parse_materials(r#"<?xml version="1.0" encoding="UTF-8" standalone="no" ?>
+        <mgns1:Message xmlns:mgns1="http://datenlotsen.de">
+          <mgns1:material>
+            <mgns1:materialID>391285431120841</mgns1:materialID>
+            <mgns1:fileName>Vorlesung_01.pdf</mgns1:fileName>
+            <mgns1:fileSize>2483200</mgns1:fileSize>
+            <mgns1:date>16.10.2023</mgns1:date>
+            <mgns1:time>09:12</mgns1:time>
+            <mgns1:courseName>Vorlesung Berechenbarkeit, Komplexität und Approximation</mgns1:courseName>
+          </mgns1:material>
+          <mgns1:material>
+            <mgns1:materialID>391285431120842</mgns1:materialID>
+            <mgns1:fileName>Blatt_01.pdf</mgns1:fileName>
+            <mgns1:fileSize/>
+            <mgns1:date/>
+            <mgns1:courseID>384875198636846</mgns1:courseID>
+          </mgns1:material>
+        </mgns1:Message>"#.to_string(), "384875198636845").unwrap();
+
+        assert_eq!(materials.len(), 2);
+
+        assert_eq!(materials[0].name, "Vorlesung_01.pdf");
+        assert_eq!(materials[0].size, Some(2_483_200));
+        assert_eq!(materials[0].course_id, "384875198636845");
+        assert_eq!(materials[0].datetime, Some(stine_naive_to_utc(
+            NaiveDate::from_ymd_opt(2023, 10, 16).unwrap().and_hms_opt(9, 12, 0).unwrap())));
+
+        assert_eq!(materials[1].size, None);
+        assert_eq!(materials[1].datetime, None);
+        assert_eq!(materials[1].course_id, "384875198636846");
+    }
+
+    #[test]
+    fn test_appointments() {
+        let appointments = parse_appointments(r#"<?xml version="1.0" encoding="UTF-8" standalone="no" ?>
+        <mgns1:Message xmlns:mgns1="http://datenlotsen.de">
+          <mgns1:appointment>
+            <mgns1:courseID>384875198636845</mgns1:courseID>
+            <mgns1:courseName>Vorlesung Berechenbarkeit, Komplexität und Approximation</mgns1:courseName>
+            <mgns1:date>24.04.2023</mgns1:date>
+            <mgns1:timeFrom>14:15</mgns1:timeFrom>
+            <mgns1:timeTo>15:45</mgns1:timeTo>
+            <mgns1:room>Erzwiss H</mgns1:room>
+            <mgns1:instructorsString>Prof. Dr. Petra Berenbrink</mgns1:instructorsString>
+          </mgns1:appointment>
+          <mgns1:appointment>
+            <mgns1:courseID>384875198636845</mgns1:courseID>
+            <mgns1:date>17.04.2023</mgns1:date>
+            <mgns1:timeFrom>14:15</mgns1:timeFrom>
+            <mgns1:timeTo>15:45</mgns1:timeTo>
+            <mgns1:room/>
+            <mgns1:instructorsString>Prof. Dr. Petra Berenbrink; Dr. Max Mustermann</mgns1:instructorsString>
+          </mgns1:appointment>
+        </mgns1:Message>"#.to_string()).unwrap();
+
+        let course_appointments = appointments.get("384875198636845").unwrap();
+        assert_eq!(course_appointments.len(), 2);
+
+        // sorted by date
+        let first = &course_appointments[0];
+        assert_eq!(first.from, Some(stine_naive_to_utc(
+            NaiveDate::from_ymd_opt(2023, 4, 17).unwrap().and_hms_opt(14, 15, 0).unwrap())));
+        assert_eq!(first.room, "");
+        assert_eq!(first.instructors.len(), 2);
+
+        assert_eq!(course_appointments[1].room, "Erzwiss H");
+    }
+
+    #[test]
+    fn test_event_info() {
+        let info = parse_event_info(r#"<?xml version="1.0" encoding="UTF-8" standalone="no" ?>
+        <mgns1:Message xmlns:mgns1="http://datenlotsen.de">
+          <mgns1:eventInfo>
+            <mgns1:courseID>384875198636845</mgns1:courseID>
+            <mgns1:eventCategory>Vorlesung</mgns1:eventCategory>
+            <mgns1:instructorsString>Prof. Dr. Petra Berenbrink</mgns1:instructorsString>
+            <mgns1:hoursPerWeek>3</mgns1:hoursPerWeek>
+            <mgns1:creditPoints>0.0000</mgns1:creditPoints>
+            <mgns1:courseLanguage>Deutsch</mgns1:courseLanguage>
+            <mgns1:minStudents>1</mgns1:minStudents>
+            <mgns1:maxStudents>240</mgns1:maxStudents>
+          </mgns1:eventInfo>
+        </mgns1:Message>"#.to_string()).unwrap();
+
+        assert_eq!(info.event_type, Some(EventType::Lecture));
+        assert_eq!(info.instructors, Some(vec!["Prof. Dr. Petra Berenbrink".to_string()]));
+        assert_eq!(info.hours_per_week, Some(3));
+        assert_eq!(info.max_participants, Some(240));
+        assert_eq!(info.attributes.unwrap().get("courseID").unwrap(), "384875198636845");
+    }
+}
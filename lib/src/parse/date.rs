@@ -161,6 +161,11 @@ pub fn parse_dmy_date(s: &str) -> ParseResult<NaiveDate> {
     NaiveDate::parse_from_str(s, "%d.%m.%y")
 }
 
+/// Parses dates with a four digit year, as used by the mobile endpoints. Example: 15.02.2024
+pub fn parse_dmy_long_date(s: &str) -> ParseResult<NaiveDate> {
+    NaiveDate::parse_from_str(s.trim(), "%d.%m.%Y")
+}
+
 pub fn parse_time(s: &str) -> ParseResult<NaiveTime> {
     NaiveTime::parse_from_str(s, "%H:%M")
 }
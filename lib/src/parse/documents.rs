@@ -63,12 +63,66 @@ pub fn parse_documents(html_content: String) -> Vec<Document> {
     documents
 }
 
+/// Decodes %XX escapes, as used by `filename*=UTF-8''...`
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = s.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Returns the file name of a Content-Disposition header.
+/// Example: attachment; filename="Semesterbescheinigung.pdf"
+pub fn parse_content_disposition_filename(header: &str) -> Option<String> {
+    let mut file_name: Option<String> = None;
+
+    for param in header.split(';').map(str::trim) {
+        if let Some(value) = param.strip_prefix("filename*=") {
+            // extended notation is preferred: filename*=UTF-8''Bescheinigung%20WiSe.pdf
+            let value = value.split_once("''").map_or(value, |(_, v)| v);
+            return Some(percent_decode(value.trim_matches('"'))).filter(|n| !n.is_empty());
+        }
+        if let Some(value) = param.strip_prefix("filename=") {
+            file_name = Some(value.trim_matches('"').to_string()).filter(|n| !n.is_empty());
+        }
+    }
+
+    file_name
+}
+
 #[cfg(test)]
 mod tests {
     use chrono::{NaiveDate};
     use crate::Document;
     use crate::parse::date::stine_naive_to_utc;
-    use crate::parse::documents::parse_documents;
+    use crate::parse::documents::{parse_content_disposition_filename, parse_documents};
+
+    #[test]
+    fn test_content_disposition() {
+        assert_eq!(parse_content_disposition_filename(r#"attachment; filename="Semesterbescheinigung.pdf""#),
+                   Some("Semesterbescheinigung.pdf".to_string()));
+        assert_eq!(parse_content_disposition_filename("inline;filename=Bescheinigung.pdf"),
+                   Some("Bescheinigung.pdf".to_string()));
+        assert_eq!(parse_content_disposition_filename(
+            r#"attachment; filename="fallback.pdf"; filename*=UTF-8''Studienbescheinigung%20%C3%9Cbersicht.pdf"#),
+                   Some("Studienbescheinigung Übersicht.pdf".to_string()));
+        assert_eq!(parse_content_disposition_filename("attachment"), None);
+    }
 
     #[test]
     fn test_document_parsing() {
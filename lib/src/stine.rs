@@ -1,19 +1,21 @@
 use std::{fs, io};
 use std::collections::HashMap;
+use std::io::{Read, Write};
 use std::path::PathBuf;
 use std::str::FromStr;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::anyhow;
 use log::trace;
 use regex::Regex;
 use reqwest::blocking::Response;
-use reqwest::header::{CONTENT_TYPE, COOKIE, HeaderMap, HeaderValue, HOST, ORIGIN, REFERER, REFRESH, SET_COOKIE, USER_AGENT};
+use reqwest::header::{CONTENT_DISPOSITION, CONTENT_TYPE, COOKIE, HeaderMap, HeaderValue, HOST, ORIGIN, REFERER, REFRESH, SET_COOKIE, USER_AGENT};
 use scraper::Html;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use thiserror::Error;
 
-use crate::{Document, GradeStats, LazyLevel, parse, utils};
+use crate::{Document, DocumentMetadata, DownloadedDocument, GradeStats, LazyLevel, parse, utils};
 use crate::{Module, ModuleCategory, SubModule};
 use crate::{Semester, SemesterResult};
 use crate::Language;
@@ -26,6 +28,12 @@ pub const BASE_URL: &str = "https://stine.uni-hamburg.de";
 
 type Client = reqwest::blocking::Client;
 
+/// Logs how long the request to `prgname` took. `prgname` and `duration_ms` are added as structured fields
+pub fn log_request_duration(method: &str, prgname: &str, start: Instant) {
+    let duration = start.elapsed();
+    log::debug!(prgname = prgname, duration_ms = duration.as_millis() as u64; "{method} {prgname} took {duration:?}");
+}
+
 #[derive(Error, Debug)]
 pub enum AuthError {
     #[error("Wrong password or username")]
@@ -132,6 +140,11 @@ impl Stine {
         self
     }
 
+    /// Cache dir used if no other dir is set using [`Stine::with_cache_dir`]
+    pub fn default_cache_dir() -> PathBuf {
+        utils::get_cache_dir()
+    }
+
     /// Set cache dir of stine
     pub fn with_cache_dir(mut self, dir: PathBuf) -> Result<Stine, io::Error> {
         self.cache_dir = dir;
@@ -253,6 +266,46 @@ impl Stine {
         Ok(parse::documents::parse_documents(resp.text()?))
     }
 
+    /// Downloads the content of the [`Document`]
+    pub fn download_document(&self, document: &Document) -> Result<DownloadedDocument, anyhow::Error> {
+        let mut content: Vec<u8> = Vec::new();
+        let metadata = self.download_document_to(document, &mut content)?;
+        Ok(DownloadedDocument { metadata, content })
+    }
+
+    /// Downloads the content of the [`Document`] and writes it to `writer`,
+    /// without holding the whole file in memory
+    pub fn download_document_to<W: Write>(&self, document: &Document, writer: &mut W)
+                                          -> Result<DocumentMetadata, anyhow::Error> {
+        let mut response = self.get(&document.download)?.error_for_status()?;
+
+        let header = |name| response.headers().get(name)
+            .and_then(|v| v.to_str().ok()).map(ToString::to_string);
+        let file_name = header(CONTENT_DISPOSITION)
+            .and_then(|h| parse::documents::parse_content_disposition_filename(&h));
+        let content_type = header(CONTENT_TYPE);
+
+        let mut hasher = Sha256::new();
+        let mut size: u64 = 0;
+        let mut buffer = [0u8; 8192];
+        loop {
+            let read = response.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            writer.write_all(&buffer[..read])?;
+            size += read as u64;
+        }
+
+        Ok(DocumentMetadata {
+            file_name,
+            content_type,
+            sha256: format!("{:x}", hasher.finalize()),
+            size,
+        })
+    }
+
     /// Returns the various Registration periods, found under Service > Registration periods
     pub fn get_registration_periods(&self) -> Result<Vec<RegistrationPeriod>, reqwest::Error> {
         let resp = self.post_with_arg("EXTERNALPAGES", vec![
@@ -333,6 +386,30 @@ impl Stine {
         };
     }
 
+    /// Returns [SubModule] by specifying its course number
+    /// # Arguments:
+    /// * course_number - the course number (LV-Nummer) of the submodule you want, example: 64-074
+    /// * force_reload -  this will parse and reload all modules you can apply for.
+    /// * lazy - Lazy loads certain info, reduces api calls and especially time to fetch the info
+    ///
+    /// **Warning**: force reloading will take roughly a few minutes
+    /// # Return:
+    /// Returns a result of either the found submodule or an error why it cant be found.
+    /// It's possible that you have to retry calling this method wih *force_reload* enabled.
+    pub fn get_submodule_by_course_number(&mut self, course_number: &str, force_reload: bool, lazy: LazyLevel)
+                                          -> Result<&SubModule, StineError> {
+        if force_reload {
+            self.get_registration_modules(true, false, lazy)?;
+        } else if self.submod_map.is_none() {
+            self.load_maps()?;
+        }
+
+        self.submod_map.as_ref().unwrap().values()
+            .find(|s| s.course_number.eq_ignore_ascii_case(course_number))
+            .ok_or_else(|| StineError::AnyError(anyhow!("SubModule not found{}",
+                if force_reload { "" } else { " maybe try force reloading" })))
+    }
+
     /// Returns [Module] by specifying its id
     /// # Arguments:
     /// * module_number - the module_number of the Module you want, example: InfB-SE 1
@@ -357,6 +434,19 @@ impl Stine {
                 .ok_or_else(|| anyhow!("Module not found"))
         };
     }
+
+    /// Returns the [Module] containing the [SubModule] with the id `submodule_id`, using the cache
+    /// # Return:
+    /// Returns an error if no cached module contains the submodule, e.g. for submodules without a module
+    pub fn get_module_of_submodule(&mut self, submodule_id: &str) -> Result<&Module, anyhow::Error> {
+        if self.mod_map.is_none() {
+            self.load_maps()?;
+        }
+        self.mod_map.as_ref().unwrap().values()
+            .find(|m| m.sub_modules.iter().any(|s| s.id == submodule_id))
+            .ok_or_else(|| anyhow!("No module contains the submodule {submodule_id}"))
+    }
+
     /// Returns exam and semester results of selected semesters
     ///
     /// **Note**: If you don't need the GradeStats please use LazyLevel::FullLazy to reduce the calls to stine
@@ -425,11 +515,16 @@ impl Stine {
         Language::from_str(html_content.root_element().value().attr("lang").unwrap()).unwrap()
     }
 
-    /// Changes your stine language to [Language]
+    /// Changes your stine language to [Language].
+    /// Does nothing, if it is already the language of the session
     /// # Returns
     /// returns whether the operation was successful
     /// in case an request error was found, the error gets returned
     pub fn set_language(&mut self, lang: &Language) -> Result<(), anyhow::Error> {
+        if self.language.as_ref() == Some(lang) {
+            return Ok(());
+        }
+
         let lang_code = match lang {
             Language::German => "-N001",
             Language::English => "-N002"
@@ -441,6 +536,7 @@ impl Stine {
             return Err(anyhow!("Failed changing STINE language"));
         }
 
+        self.language = Some(lang.clone());
         Ok(())
     }
 
@@ -492,7 +588,10 @@ impl Stine {
         ]);
 
         log::debug!("POST to: {prgname} {args_str}");
-        self.post(API_URL, params)
+        let start = Instant::now();
+        let response = self.post(API_URL, params);
+        log_request_duration("POST", prgname, start);
+        response
     }
 
     pub fn get_with_arg(&self, prg_name: &str, args: Vec<String>) -> Result<Response, reqwest::Error> {
@@ -510,7 +609,10 @@ impl Stine {
 
 
         log::debug!("GET to: {url}, \nArguments: {args:#?}");
-        self.client.get(url).headers(headers).send()
+        let start = Instant::now();
+        let response = self.client.get(url).headers(headers).send();
+        log_request_duration("GET", prg_name, start);
+        response
     }
 
     pub fn get(&self, url: &str) -> reqwest::Result<Response> {
@@ -600,3 +702,47 @@ pub struct MyRegistrations {
     pub accepted_modules: Vec<Module>,
 }
 
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{LazyLoaded, Module, Stine, SubModule};
+
+    fn submodule(id: &str) -> SubModule {
+        SubModule {
+            id: id.to_string(),
+            course_number: format!("64-{id}"),
+            name: format!("64-{id} Vorlesung"),
+            info: LazyLoaded::unloaded(String::new()),
+            appointments: LazyLoaded::unloaded(String::new()),
+            groups: LazyLoaded::unloaded(String::new()),
+        }
+    }
+
+    fn module(module_number: &str, sub_modules: Vec<SubModule>) -> Module {
+        Module {
+            module_number: module_number.to_string(),
+            name: "Softwareentwicklung".to_string(),
+            sub_modules,
+            exams: vec![],
+            owner: String::new(),
+            timetable_name: None,
+            duration: None,
+            electives: None,
+            credits: Some("9,0".to_string()),
+            start_semester: None,
+            attributes: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_get_module_of_submodule() {
+        let mut stine = Stine::default();
+        stine.add_module(module("InfB-SE 1", vec![submodule("1"), submodule("2")]));
+        stine.add_module(module("InfB-SE 2", vec![submodule("3")]));
+
+        assert_eq!(stine.get_module_of_submodule("2").unwrap().module_number, "InfB-SE 1");
+        assert_eq!(stine.get_module_of_submodule("3").unwrap().module_number, "InfB-SE 2");
+        assert!(stine.get_module_of_submodule("4").is_err());
+    }
+}
@@ -11,6 +11,26 @@ pub struct Document {
     pub download: String,
 }
 
+/// Info about a downloaded [`Document`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DocumentMetadata {
+    /// File name sent by the server in the `Content-Disposition` header
+    pub file_name: Option<String>,
+    /// MIME type, e.g. application/pdf
+    pub content_type: Option<String>,
+    /// Hex encoded SHA-256 hash of the content
+    pub sha256: String,
+    /// Size in bytes
+    pub size: u64,
+}
+
+/// Content of a [`Document`]. See [`crate::Stine::download_document`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadedDocument {
+    pub metadata: DocumentMetadata,
+    pub content: Vec<u8>,
+}
+
 impl Hash for Document {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.name.hash(state);
@@ -199,12 +199,19 @@ impl Display for SubModule {
 impl SubModule {
     /// Loads [`CourseInfo`], [`Appointment`] and [`Group`]
     pub fn lazy_load(&mut self, stine: &Stine) {
+        self.try_lazy_load(stine).unwrap();
+    }
+
+    /// Loads [`CourseInfo`], [`Appointment`] and [`Group`]
+    /// # Errors
+    /// Will error if the course details can't be requested
+    pub fn try_lazy_load(&mut self, stine: &Stine) -> Result<(), anyhow::Error> {
         let link = self.appointments.link.clone();
 
         let resp = stine.post_with_arg(
             "COURSEDETAILS",
-            utils::parse_arg_string(self.info.link.as_str())).unwrap();
-        let html = Html::parse_fragment(&resp.text().unwrap());
+            utils::parse_arg_string(self.info.link.as_str()))?;
+        let html = Html::parse_fragment(&resp.text()?);
 
         // Loads `info`
         let course_info = parse::parse_course_info(&html, stine);
@@ -215,6 +222,7 @@ impl SubModule {
 
         // Loads `appointments` and `groups`
         parse::parse_tables(html, self, stine, NotLazy, link);
+        Ok(())
     }
 
     /// returns [`CourseInfo`]
@@ -265,6 +273,14 @@ impl SubModule {
         }
     }
 
+    /// Like [`SubModule::appointments`], but returns an error if loading them fails
+    pub fn try_appointments(&mut self, stine: &Stine) -> Result<Option<Vec<Appointment>>, anyhow::Error> {
+        if !self.appointments_loaded() {
+            self.try_lazy_load(stine)?;
+        }
+        Ok(self.appointments.unwrap().as_ref().cloned())
+    }
+
     /// returns [`Group`]
     /// # Side effects:
     /// Loads [`CourseInfo`], [`Appointment`] and [`Group`]
@@ -332,6 +348,12 @@ pub struct CourseResult {
 }
 
 impl CourseResult {
+    /// Returns the id used to fetch the [`GradeStats`] of this course,
+    /// e.g. for [`Stine::get_grade_stats_for_exam`]. `None` if STINE has no stats for this course
+    pub fn grade_stats_id(&self) -> Option<&str> {
+        self.grade_stats.as_ref().map(|g| g.link.as_str())
+    }
+
     pub fn get_grade_stats(&mut self, stine: &Stine) -> Option<GradeStats> {
         if let Some(grade_stats) = &self.grade_stats {
             match &grade_stats.status {
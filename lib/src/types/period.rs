@@ -9,6 +9,13 @@ pub struct Period {
     pub end: DateTime<Utc>,
 }
 
+impl Period {
+    /// Checks if `datetime` lies between start and end (inclusive)
+    pub fn contains(&self, datetime: &DateTime<Utc>) -> bool {
+        self.start <= *datetime && *datetime <= self.end
+    }
+}
+
 impl ToString for Period {
     fn to_string(&self) -> String {
         format!("{} - {}",
@@ -1,7 +1,9 @@
 use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
 use std::str::FromStr;
 
 use anyhow::anyhow;
+use chrono::NaiveDate;
 use either::{Either, Left, Right};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
@@ -60,6 +62,28 @@ impl Semester {
             year: Right((year1, year2)),
         }
     }
+
+    /// Returns the first and last day of the semester.
+    /// Summer semesters last from April to September, winter semesters from October to March
+    pub fn date_range(&self) -> RangeInclusive<NaiveDate> {
+        // years are usually abbreviated: SoSe 22
+        let full_year = |year: i32| if year < 100 { year + 2000 } else { year };
+
+        let (start_year, end_year) = match self.year {
+            Left(year) => (full_year(year), full_year(year)),
+            Right((year1, year2)) => (full_year(year1), full_year(year2)),
+        };
+
+        match self.season {
+            SemesterType::SummerSemester => NaiveDate::from_ymd_opt(start_year, 4, 1).unwrap()
+                ..=NaiveDate::from_ymd_opt(start_year, 9, 30).unwrap(),
+            SemesterType::WinterSemester => {
+                let end_year = if end_year > start_year { end_year } else { start_year + 1 };
+                NaiveDate::from_ymd_opt(start_year, 10, 1).unwrap()
+                    ..=NaiveDate::from_ymd_opt(end_year, 3, 31).unwrap()
+            }
+        }
+    }
 }
 
 impl Display for Semester {
@@ -112,6 +136,8 @@ impl FromStr for Semester {
 mod tests {
     use std::str::FromStr;
 
+    use chrono::NaiveDate;
+
     use crate::Semester;
     use crate::SemesterType::{SummerSemester, WinterSemester};
 
@@ -134,6 +160,17 @@ mod tests {
         assert_eq!(s.year.right().unwrap(), (20, 23));
     }
 
+    #[test]
+    pub fn test_date_range() {
+        let range = Semester::new_winter(22, 23).date_range();
+        assert_eq!(*range.start(), NaiveDate::from_ymd_opt(2022, 10, 1).unwrap());
+        assert_eq!(*range.end(), NaiveDate::from_ymd_opt(2023, 3, 31).unwrap());
+
+        let range = Semester::new_summer(24).date_range();
+        assert_eq!(*range.start(), NaiveDate::from_ymd_opt(2024, 4, 1).unwrap());
+        assert_eq!(*range.end(), NaiveDate::from_ymd_opt(2024, 9, 30).unwrap());
+    }
+
     #[test]
     pub fn test_parse_semester_string_invalid() {
         Semester::from_str("wise").unwrap_err();
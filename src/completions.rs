@@ -0,0 +1,311 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use chrono::{Datelike, Local, NaiveDate};
+use clap::{arg, Arg, ArgMatches, Command, value_parser};
+use clap_complete::Shell;
+use colored::Colorize;
+use serde::Deserialize;
+
+/// Number of semesters offered for completion, starting with the current one
+const SEMESTER_COUNT: i32 = 12;
+
+pub(crate) fn completions_subcommand() -> Command {
+    Command::new("completions")
+        .about("Print the completion script for your shell. Doesn't require authentication")
+        .arg(arg!([SHELL] "Shell to complete in, e.g. bash, zsh or fish")
+            .required_unless_present("values")
+            .value_parser(value_parser!(Shell)))
+        // called by the completion scripts to complete semesters and course numbers
+        .arg(Arg::new("values").long("values")
+            .hide(true)
+            .value_parser(["semesters", "courses"]))
+}
+
+pub(crate) fn man_subcommand() -> Command {
+    Command::new("man")
+        .about("Print the man page of stine-cli or one of its subcommands. Doesn't require authentication")
+        .arg(arg!([COMMAND] ... "Subcommand to print the man page of, e.g. `course show`")
+            .value_parser(value_parser!(String)))
+        .arg(arg!(-o --"out-dir" <DIR> "Write the man pages of stine-cli and all subcommands to this directory instead")
+            .required(false)
+            .conflicts_with("COMMAND")
+            .value_parser(value_parser!(PathBuf)))
+}
+
+/// Returns the current and previous semesters, e.g. `SuSe24` and `WiSe23/24`
+fn recent_semesters(today: NaiveDate) -> Vec<String> {
+    // winter semesters start in october and end in march
+    let (mut year, mut summer) = match today.month() {
+        1..=3 => (today.year() - 1, false),
+        4..=9 => (today.year(), true),
+        _ => (today.year(), false),
+    };
+
+    let mut semesters = Vec::new();
+    for _ in 0..SEMESTER_COUNT {
+        if summer {
+            semesters.push(format!("SuSe{:02}", year % 100));
+            year -= 1;
+        } else {
+            semesters.push(format!("WiSe{:02}/{:02}", year % 100, (year + 1) % 100));
+        }
+        summer = !summer;
+    }
+    semesters
+}
+
+#[derive(Deserialize)]
+struct CachedCourse {
+    course_number: String,
+}
+
+/// Returns the course numbers of all cached courses, in every language
+fn cached_course_numbers(cache_dir: &Path) -> BTreeSet<String> {
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return BTreeSet::new();
+    };
+
+    entries.filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("submodules_"))
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .filter_map(|data| serde_json::from_str::<HashMap<String, CachedCourse>>(&data).ok())
+        .flat_map(HashMap::into_values)
+        .map(|course| course.course_number)
+        .filter(|number| !number.is_empty() && !number.contains(char::is_whitespace))
+        .collect()
+}
+
+/// Completion of the values, which depend on the date or the cache. Wraps the generated completion function
+fn dynamic_script(shell: Shell, bin: &str) -> Option<String> {
+    let function = format!("_{bin}_values");
+    match shell {
+        Shell::Bash => Some(format!(r#"
+{function}() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}" prev="${{COMP_WORDS[COMP_CWORD-1]}}" kind=""
+    if [[ "${{prev}}" == "-s" || "${{prev}}" == "--semesters" ]]; then
+        kind="semesters"
+    elif [[ "${{prev}}" == "grade-stats" || ( "${{prev}}" == "show" && "${{COMP_WORDS[COMP_CWORD-2]}}" == "course" ) ]]; then
+        kind="courses"
+    fi
+    if [[ -n "${{kind}}" && "${{cur}}" != -* ]]; then
+        COMPREPLY=( $(compgen -W "$({bin} completions --values ${{kind}} 2>/dev/null)" -- "${{cur}}") )
+        return 0
+    fi
+    _{bin} "$@"
+}}
+
+complete -F {function} -o bashdefault -o default {bin}
+"#)),
+        Shell::Zsh => Some(format!(r#"
+{function}() {{
+    local prev="${{words[CURRENT-1]}}" kind=""
+    if [[ "$prev" == "-s" || "$prev" == "--semesters" ]]; then
+        kind="semesters"
+    elif [[ "$prev" == "grade-stats" || ( "$prev" == "show" && "${{words[CURRENT-2]}}" == "course" ) ]]; then
+        kind="courses"
+    fi
+    if [[ -n "$kind" && "${{words[CURRENT]}}" != -* ]]; then
+        compadd -- ${{(f)"$({bin} completions --values $kind 2>/dev/null)"}}
+        return 0
+    fi
+    _{bin} "$@"
+}}
+
+compdef {function} {bin}
+"#)),
+        Shell::Fish => Some(format!(r#"
+complete -c {bin} -n "__fish_seen_subcommand_from semester-results; and __fish_prev_arg_in -s --semesters" -f -a "({bin} completions --values semesters 2>/dev/null)"
+complete -c {bin} -n "__fish_seen_subcommand_from grade-stats" -f -a "({bin} completions --values courses 2>/dev/null)"
+complete -c {bin} -n "__fish_seen_subcommand_from course; and __fish_seen_subcommand_from show" -f -a "({bin} completions --values courses 2>/dev/null)"
+"#)),
+        _ => None,
+    }
+}
+
+/// Generates the completion script of `command` for `shell`
+fn completion_script(shell: Shell, mut command: Command) -> String {
+    let bin = command.get_name().to_string();
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut command, &bin, &mut script);
+
+    let mut script = String::from_utf8(script).unwrap();
+    if let Some(dynamic) = dynamic_script(shell, &bin) {
+        script.push_str(&dynamic);
+    }
+    script
+}
+
+/// Writes `output` to `out`. A closed pipe, e.g. of `| head`, isn't an error
+fn write_output(out: &mut dyn Write, output: &[u8]) -> io::Result<()> {
+    match out.write_all(output).and_then(|()| out.flush()) {
+        Err(err) if err.kind() == ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+fn lines<T: AsRef<str>>(values: impl IntoIterator<Item=T>) -> String {
+    values.into_iter().map(|value| format!("{}\n", value.as_ref())).collect()
+}
+
+pub(crate) fn completions_command(sub_matches: &ArgMatches, command: Command, cache_dir: &Path) -> anyhow::Result<()> {
+    let output = match sub_matches.get_one::<String>("values").map(String::as_str) {
+        Some("semesters") => lines(recent_semesters(Local::now().date_naive())),
+        Some("courses") => lines(cached_course_numbers(cache_dir)),
+        _ => {
+            let shell = *sub_matches.get_one::<Shell>("SHELL").unwrap();
+            completion_script(shell, command)
+        }
+    };
+    write_output(&mut io::stdout().lock(), output.as_bytes()).context("Failed printing completions")
+}
+
+/// Returns `command` and all of its subcommands, except the generated help commands
+fn man_commands(command: &Command) -> Vec<&Command> {
+    let mut commands = vec![command];
+    for subcommand in command.get_subcommands().filter(|c| c.get_name() != "help" && !c.is_hide_set()) {
+        commands.extend(man_commands(subcommand));
+    }
+    commands
+}
+
+fn render_man_page(command: &Command, out: &mut dyn Write) -> io::Result<()> {
+    // subcommands have no version, which would be missing in the footer
+    clap_mangen::Man::new(command.clone())
+        .source(format!("stine-cli {}", env!("CARGO_PKG_VERSION")))
+        .render(out)
+}
+
+/// Writes the man pages of `command` and all subcommands to `dir`, e.g. `stine-cli-course-show.1`.
+/// Returns the paths of the written pages
+fn write_man_pages(command: &Command, dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    man_commands(command).into_iter()
+        .map(|command| {
+            let name = command.get_display_name().unwrap_or_else(|| command.get_name());
+            let path = dir.join(format!("{name}.1"));
+            let mut page = Vec::new();
+            render_man_page(command, &mut page)?;
+            fs::write(&path, page)?;
+            Ok(path)
+        })
+        .collect()
+}
+
+pub(crate) fn man_command(sub_matches: &ArgMatches, mut command: Command) -> anyhow::Result<()> {
+    // sets the names of the subcommands in their pages, e.g. stine-cli-course-show
+    command.build();
+
+    if let Some(dir) = sub_matches.get_one::<PathBuf>("out-dir") {
+        let paths = write_man_pages(&command, dir)
+            .with_context(|| format!("Failed writing man pages to {}", dir.display()))?;
+        eprintln!("{} [{}]", format!("> Wrote {} man pages", paths.len()).bright_green(),
+                  dir.display().to_string().underline());
+        return Ok(());
+    }
+
+    let mut page = &command;
+    for name in sub_matches.get_many::<String>("COMMAND").into_iter().flatten() {
+        page = page.find_subcommand(name).ok_or_else(|| anyhow!("Unknown subcommand: {name}"))?;
+    }
+    let mut output = Vec::new();
+    render_man_page(page, &mut output)?;
+    write_output(&mut io::stdout().lock(), &output).context("Failed printing man page")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::{self, ErrorKind, Write};
+
+    use chrono::NaiveDate;
+    use clap_complete::Shell;
+
+    use crate::completions::{cached_course_numbers, completion_script, lines, recent_semesters, write_man_pages, write_output};
+    use crate::get_command;
+
+    #[test]
+    fn test_recent_semesters() {
+        let semesters = recent_semesters(NaiveDate::from_ymd_opt(2024, 2, 1).unwrap());
+        assert_eq!(&semesters[..3], ["WiSe23/24", "SuSe23", "WiSe22/23"]);
+        assert_eq!(semesters.len(), 12);
+
+        let semesters = recent_semesters(NaiveDate::from_ymd_opt(2024, 5, 1).unwrap());
+        assert_eq!(&semesters[..2], ["SuSe24", "WiSe23/24"]);
+        assert_eq!(recent_semesters(NaiveDate::from_ymd_opt(2024, 11, 1).unwrap())[0], "WiSe24/25");
+
+        // valid arguments of --semesters
+        for semester in semesters {
+            assert!(semester.parse::<stine_rs::Semester>().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_cached_course_numbers() {
+        let dir = std::env::temp_dir().join("stine-cli-completions-test");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("submodules_de.json"),
+                  r#"{"1": {"id": "1", "course_number": "64-040", "name": "64-040 Einführung"},
+                      "2": {"id": "2", "course_number": "", "name": "Unknown"}}"#).unwrap();
+        fs::write(dir.join("submodules_en.json"),
+                  r#"{"1": {"id": "1", "course_number": "64-040", "name": "64-040 Introduction"},
+                      "3": {"id": "3", "course_number": "64-074", "name": "64-074 Software"}}"#).unwrap();
+        fs::write(dir.join("modules_en.json"), "{}").unwrap();
+
+        assert_eq!(cached_course_numbers(&dir).into_iter().collect::<Vec<_>>(), ["64-040", "64-074"]);
+        assert!(cached_course_numbers(&dir.join("missing")).is_empty());
+    }
+
+    #[test]
+    fn test_completion_script() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let script = completion_script(shell, get_command());
+            assert!(script.contains("semester-results"));
+            assert!(script.contains("stine-cli completions --values"));
+        }
+        assert!(!completion_script(Shell::PowerShell, get_command()).contains("completions --values"));
+    }
+
+    struct ClosedPipe(ErrorKind);
+
+    impl Write for ClosedPipe {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(self.0.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_output() {
+        assert_eq!(lines(["SuSe24", "WiSe23/24"]), "SuSe24\nWiSe23/24\n");
+
+        let mut out = Vec::new();
+        write_output(&mut out, b"64-040\n").unwrap();
+        assert_eq!(out, b"64-040\n");
+
+        // e.g. `stine-cli completions bash | head`
+        assert!(write_output(&mut ClosedPipe(ErrorKind::BrokenPipe), b"64-040\n").is_ok());
+        assert!(write_output(&mut ClosedPipe(ErrorKind::PermissionDenied), b"64-040\n").is_err());
+    }
+
+    #[test]
+    fn test_write_man_pages() {
+        let dir = std::env::temp_dir().join("stine-cli-man-test");
+        let mut command = get_command();
+        command.build();
+
+        let paths = write_man_pages(&command, &dir).unwrap();
+        assert!(paths.contains(&dir.join("stine-cli.1")));
+        assert!(paths.contains(&dir.join("stine-cli-course-show.1")));
+        assert!(!paths.iter().any(|path| path.ends_with("stine-cli-help.1")));
+
+        let page = fs::read_to_string(dir.join("stine-cli-course-show.1")).unwrap();
+        assert!(page.contains(".TH stine-cli-course-show"));
+    }
+}
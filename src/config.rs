@@ -0,0 +1,754 @@
+use std::collections::BTreeMap;
+use std::{env, fmt};
+use std::ffi::OsString;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use chrono::Utc;
+use clap::{ArgMatches, Command, ValueEnum};
+use colored::Colorize;
+use lazy_static::lazy_static;
+use log::info;
+use prettytable::{row, Table};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use stine_rs::Stine;
+
+use crate::{Language, logging, notify, output, password, redact, store};
+use crate::logging::{DEFAULT_LOG_PATH, LogConfig, LogFormat, LogLevel};
+use crate::notify::NotifyEvent;
+use crate::output::OutputFormat;
+use crate::store::{ProfileSecrets, SecretStore, STORE_PATH};
+
+lazy_static! {
+    /// Config file containing all profiles, e.g. ~/.config/stine-cli/config.toml
+    pub(crate) static ref CONFIG_PATH: PathBuf = xdg_config_dir(env::var_os("XDG_CONFIG_HOME"), dirs::home_dir())
+        .join("stine-cli").join("config.toml");
+    /// Session cookies of all profiles. Kept out of the config file, as they change with every login
+    pub(crate) static ref SESSIONS_PATH: PathBuf = dirs::data_dir().unwrap().join("stine-cli").join("sessions.toml");
+    /// Config of previous versions, next to the executable
+    pub(crate) static ref LEGACY_CONFIG_PATH: PathBuf = env::current_exe().unwrap().parent().unwrap().join(".stine-env");
+}
+
+pub(crate) const DEFAULT_PROFILE: &str = "default";
+
+/// `$XDG_CONFIG_HOME`, or `~/.config` if it is unset or not absolute. Also used on macOS and windows
+fn xdg_config_dir(xdg_config_home: Option<OsString>, home: Option<PathBuf>) -> PathBuf {
+    xdg_config_home.map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .unwrap_or_else(|| home.expect("Failed finding the home directory").join(".config"))
+}
+
+/// Content of the config file
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
+pub(crate) struct ConfigFile {
+    /// Profile used if no `--profile` is passed. Defaults to [`DEFAULT_PROFILE`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+    /// Passwords and sessions are stored in the encrypted store instead. See `config encrypt`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub encrypted_store: bool,
+    /// Command printing the passphrase of the encrypted store
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase_command: Option<String>,
+    /// Log file settings, shared by all profiles
+    #[serde(default, skip_serializing_if = "LogConfig::is_default")]
+    pub log: LogConfig,
+    #[serde(default)]
+    pub profile: BTreeMap<String, Profile>,
+}
+
+/// Settings of a `[profile.<name>]` section
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+#[serde(default)]
+pub(crate) struct Profile {
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Command printing the password, e.g. `pass show stine`
+    pub password_command: Option<String>,
+    pub language: Option<Language>,
+    pub output: Option<OutputFormat>,
+    /// Cache of the scraped courses and modules
+    pub cache_dir: Option<PathBuf>,
+    pub notify: NotifyProfile,
+}
+
+/// Defaults for the `notify` command. Command line arguments are prioritized
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+#[serde(default)]
+pub(crate) struct NotifyProfile {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<NotifyEvent>,
+    /// Directory of the data used to detect changes
+    pub dir: Option<PathBuf>,
+    pub email_address: Option<String>,
+    pub email_password: Option<String>,
+    pub email_password_command: Option<String>,
+    pub smtp_server: Option<String>,
+    pub smtp_port: Option<u16>,
+}
+
+pub(crate) struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// Session cookies of a profile
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub(crate) struct Session {
+    pub session: String,
+    pub cnsc_cookie: String,
+    /// Last usage of session cookies as unix timestamp
+    pub last_used: Option<i64>,
+}
+
+/// `.stine-env` of previous versions, containing credentials and session
+#[derive(Debug, Deserialize, Default)]
+struct LegacyConfig {
+    username: String,
+    password: String,
+    session: String,
+    cnsc_cookie: String,
+    last_used: Option<i64>,
+}
+
+impl ConfigFile {
+    pub(crate) fn get_profile(&self, name: &str) -> Profile {
+        self.profile.get(name).cloned().unwrap_or_default()
+    }
+}
+
+/// Origin of a setting. Later variants override earlier ones
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Source {
+    Default,
+    ConfigFile,
+    Env(&'static str),
+    Flag(&'static str),
+    /// Output of a password command
+    Command,
+    Stdin,
+    Prompt,
+    Store,
+}
+
+impl Source {
+    pub(crate) fn priority(&self) -> u8 {
+        match self {
+            Source::Default => 0,
+            Source::ConfigFile | Source::Store => 1,
+            Source::Env(_) => 2,
+            Source::Flag(_) | Source::Command | Source::Stdin | Source::Prompt => 3,
+        }
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::ConfigFile => write!(f, "config file"),
+            Source::Env(name) => write!(f, "env {name}"),
+            Source::Flag(name) => write!(f, "flag {name}"),
+            Source::Command => write!(f, "password command"),
+            Source::Stdin => write!(f, "stdin"),
+            Source::Prompt => write!(f, "prompt"),
+            Source::Store => write!(f, "encrypted store"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Setting<T> {
+    pub value: Option<T>,
+    pub source: Source,
+}
+
+impl<T> Setting<T> {
+    fn new(value: Option<T>) -> Self {
+        Setting { value, source: Source::Default }
+    }
+
+    /// Overrides the value, if `value` is set
+    fn layer(self, value: Option<T>, source: Source) -> Self {
+        match value {
+            Some(value) => Setting { value: Some(value), source },
+            None => self,
+        }
+    }
+}
+
+/// All settings, resolved from defaults, the config file, `STINE_*` environment variables and flags
+#[derive(Debug, Clone)]
+pub(crate) struct Settings {
+    pub profile: Setting<String>,
+    pub username: Setting<String>,
+    pub password: Setting<String>,
+    pub password_command: Setting<String>,
+    pub language: Setting<Language>,
+    pub output: Setting<OutputFormat>,
+    pub cache_dir: Setting<PathBuf>,
+    pub notify_dir: Setting<PathBuf>,
+    pub notify_events: Setting<Vec<NotifyEvent>>,
+    pub email_address: Setting<String>,
+    pub email_password: Setting<String>,
+    pub email_password_command: Setting<String>,
+    pub smtp_server: Setting<String>,
+    pub smtp_port: Setting<u16>,
+    pub encrypted_store: Setting<bool>,
+    pub passphrase: Setting<String>,
+    pub passphrase_command: Setting<String>,
+    pub log_path: Setting<PathBuf>,
+    pub log_level: Setting<LogLevel>,
+    pub log_format: Setting<LogFormat>,
+    pub log_targets: Setting<Vec<String>>,
+    pub log_max_size: Setting<u64>,
+    pub log_max_age_days: Setting<u64>,
+    pub log_keep: Setting<usize>,
+}
+
+/// Reads and parses the environment variable `name` using `parse`
+fn env_value<T, F>(env: &impl Fn(&str) -> Option<String>, name: &'static str, parse: F) -> anyhow::Result<Option<T>>
+    where F: Fn(&str) -> Result<T, String> {
+    env(name)
+        .filter(|value| !value.is_empty())
+        .map(|value| parse(&value).map_err(|err| anyhow!("Invalid value of {name}: {err}")))
+        .transpose()
+}
+
+fn parse_string(value: &str) -> Result<String, String> {
+    Ok(value.to_string())
+}
+
+fn parse_value_enum<T: ValueEnum>(value: &str) -> Result<T, String> {
+    T::from_str(value, true)
+}
+
+/// Comma separated list, e.g. `stine,reqwest`
+fn parse_list(value: &str) -> Result<Vec<String>, String> {
+    Ok(value.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect())
+}
+
+/// Comma separated list, e.g. `exam-result,documents`
+fn parse_events(value: &str) -> Result<Vec<NotifyEvent>, String> {
+    value.split(',').map(|event| parse_value_enum(event.trim())).collect()
+}
+
+impl Settings {
+    /// Resolves all settings. Environment variables are read using `env`
+    pub(crate) fn resolve(matches: &ArgMatches, config: &ConfigFile, env: impl Fn(&str) -> Option<String>)
+                          -> anyhow::Result<Settings> {
+        let flag = |id: &str| matches.get_one::<String>(id).cloned();
+        // notify settings are only available as flags of the notify command
+        let notify_matches = matches.subcommand_matches("notify");
+        let notify_flag = |id: &str| notify_matches.and_then(|m| m.get_one::<String>(id)).cloned();
+
+        let profile = Setting::new(Some(DEFAULT_PROFILE.to_string()))
+            .layer(config.default_profile.clone(), Source::ConfigFile)
+            .layer(env_value(&env, "STINE_PROFILE", parse_string)?, Source::Env("STINE_PROFILE"))
+            .layer(flag("profile"), Source::Flag("--profile"));
+        let file = config.get_profile(profile.value.as_ref().unwrap());
+
+        Ok(Settings {
+            username: Setting::new(None)
+                .layer(file.username, Source::ConfigFile)
+                .layer(env_value(&env, "STINE_USERNAME", parse_string)?, Source::Env("STINE_USERNAME"))
+                .layer(flag("username"), Source::Flag("--username")),
+            password: Setting::new(None)
+                .layer(file.password, Source::ConfigFile)
+                .layer(env_value(&env, "STINE_PASSWORD", parse_string)?, Source::Env("STINE_PASSWORD"))
+                .layer(flag("password"), Source::Flag("--password")),
+            password_command: Setting::new(None)
+                .layer(file.password_command, Source::ConfigFile)
+                .layer(env_value(&env, "STINE_PASSWORD_COMMAND", parse_string)?, Source::Env("STINE_PASSWORD_COMMAND")),
+            language: Setting::new(None)
+                .layer(file.language, Source::ConfigFile)
+                .layer(env_value(&env, "STINE_LANGUAGE", parse_value_enum)?, Source::Env("STINE_LANGUAGE"))
+                .layer(matches.get_one::<Language>("language").cloned(), Source::Flag("--language")),
+            output: Setting::new(Some(OutputFormat::default()))
+                .layer(file.output, Source::ConfigFile)
+                .layer(env_value(&env, "STINE_OUTPUT", parse_value_enum)?, Source::Env("STINE_OUTPUT"))
+                .layer(matches.get_one::<OutputFormat>(output::FORMAT_ARG).copied(), Source::Flag("--output")),
+            cache_dir: Setting::new(Some(Stine::default_cache_dir()))
+                .layer(file.cache_dir, Source::ConfigFile)
+                .layer(env_value(&env, "STINE_CACHE_DIR", |v| Ok(PathBuf::from(v)))?, Source::Env("STINE_CACHE_DIR"))
+                .layer(matches.get_one::<PathBuf>("cache-dir").cloned(), Source::Flag("--cache-dir")),
+            notify_dir: Setting::new(Some(notify::default_notify_dir()))
+                .layer(file.notify.dir, Source::ConfigFile)
+                .layer(env_value(&env, "STINE_NOTIFY_DIR", |v| Ok(PathBuf::from(v)))?, Source::Env("STINE_NOTIFY_DIR"))
+                .layer(notify_matches.and_then(|m| m.get_one::<PathBuf>("notify-dir")).cloned(),
+                       Source::Flag("--notify-dir")),
+            notify_events: Setting::new(None)
+                .layer(Some(file.notify.events).filter(|e| !e.is_empty()), Source::ConfigFile)
+                .layer(env_value(&env, "STINE_NOTIFY_EVENTS", parse_events)?, Source::Env("STINE_NOTIFY_EVENTS"))
+                .layer(notify_matches.and_then(|m| m.get_many::<NotifyEvent>("events"))
+                           .map(|events| events.copied().collect()), Source::Flag("--events")),
+            email_address: Setting::new(None)
+                .layer(file.notify.email_address, Source::ConfigFile)
+                .layer(env_value(&env, "STINE_EMAIL_ADDRESS", parse_string)?, Source::Env("STINE_EMAIL_ADDRESS"))
+                .layer(notify_flag("email_address"), Source::Flag("--email_address")),
+            email_password: Setting::new(None)
+                .layer(file.notify.email_password, Source::ConfigFile)
+                .layer(env_value(&env, "STINE_EMAIL_PASSWORD", parse_string)?, Source::Env("STINE_EMAIL_PASSWORD"))
+                .layer(notify_flag("email_password"), Source::Flag("--email_password")),
+            email_password_command: Setting::new(None)
+                .layer(file.notify.email_password_command, Source::ConfigFile)
+                .layer(env_value(&env, "STINE_EMAIL_PASSWORD_COMMAND", parse_string)?,
+                       Source::Env("STINE_EMAIL_PASSWORD_COMMAND")),
+            smtp_server: Setting::new(None)
+                .layer(file.notify.smtp_server, Source::ConfigFile)
+                .layer(env_value(&env, "STINE_SMTP_SERVER", parse_string)?, Source::Env("STINE_SMTP_SERVER"))
+                .layer(notify_flag("smtp_server"), Source::Flag("--smtp_server")),
+            smtp_port: Setting::new(None)
+                .layer(file.notify.smtp_port, Source::ConfigFile)
+                .layer(env_value(&env, "STINE_SMTP_PORT", |v| v.parse::<u16>().map_err(|e| e.to_string()))?,
+                       Source::Env("STINE_SMTP_PORT"))
+                .layer(notify_matches.and_then(|m| m.get_one::<u16>("smtp_port")).copied(),
+                       Source::Flag("--smtp_port")),
+            encrypted_store: Setting::new(Some(false))
+                .layer(Some(true).filter(|_| config.encrypted_store), Source::ConfigFile)
+                .layer(env_value(&env, "STINE_ENCRYPTED_STORE", |v| v.parse::<bool>().map_err(|e| e.to_string()))?,
+                       Source::Env("STINE_ENCRYPTED_STORE")),
+            passphrase: Setting::new(None)
+                .layer(env_value(&env, "STINE_PASSPHRASE", parse_string)?, Source::Env("STINE_PASSPHRASE")),
+            passphrase_command: Setting::new(None)
+                .layer(config.passphrase_command.clone(), Source::ConfigFile)
+                .layer(env_value(&env, "STINE_PASSPHRASE_COMMAND", parse_string)?, Source::Env("STINE_PASSPHRASE_COMMAND")),
+            log_path: Setting::new(Some(DEFAULT_LOG_PATH.clone()))
+                .layer(config.log.path.clone(), Source::ConfigFile)
+                .layer(env_value(&env, "STINE_LOG_PATH", |v| Ok(PathBuf::from(v)))?, Source::Env("STINE_LOG_PATH")),
+            log_level: Setting::new(Some(LogLevel::Trace))
+                .layer(config.log.level, Source::ConfigFile)
+                .layer(env_value(&env, "STINE_LOG_LEVEL", parse_value_enum)?, Source::Env("STINE_LOG_LEVEL")),
+            log_format: Setting::new(Some(LogFormat::default()))
+                .layer(config.log.format, Source::ConfigFile)
+                .layer(env_value(&env, "STINE_LOG_FORMAT", parse_value_enum)?, Source::Env("STINE_LOG_FORMAT")),
+            log_targets: Setting::new(Some(vec!["stine".to_string()]))
+                .layer(Some(config.log.targets.clone()).filter(|t| !t.is_empty()), Source::ConfigFile)
+                .layer(env_value(&env, "STINE_LOG_TARGETS", parse_list)?, Source::Env("STINE_LOG_TARGETS")),
+            log_max_size: Setting::new(Some(logging::DEFAULT_MAX_SIZE))
+                .layer(config.log.max_size.as_deref().map(logging::parse_size).transpose()
+                           .map_err(|err| anyhow!("Invalid value of log.max_size: {err}"))?, Source::ConfigFile)
+                .layer(env_value(&env, "STINE_LOG_MAX_SIZE", logging::parse_size)?, Source::Env("STINE_LOG_MAX_SIZE")),
+            log_max_age_days: Setting::new(None)
+                .layer(config.log.max_age_days, Source::ConfigFile)
+                .layer(env_value(&env, "STINE_LOG_MAX_AGE_DAYS", |v| v.parse::<u64>().map_err(|e| e.to_string()))?, Source::Env("STINE_LOG_MAX_AGE_DAYS")),
+            log_keep: Setting::new(Some(logging::DEFAULT_KEEP))
+                .layer(config.log.keep, Source::ConfigFile)
+                .layer(env_value(&env, "STINE_LOG_KEEP", |v| v.parse::<usize>().map_err(|e| e.to_string()))?, Source::Env("STINE_LOG_KEEP")),
+            profile,
+        })
+    }
+
+    pub(crate) fn profile_name(&self) -> &str {
+        self.profile.value.as_deref().unwrap_or(DEFAULT_PROFILE)
+    }
+
+    /// Reads the password from stdin, the password command or a hidden prompt. See [`password::resolve_secret`]
+    pub(crate) fn read_password(&mut self, stdin: Option<&mut dyn BufRead>, interactive: bool) -> anyhow::Result<()> {
+        let prompt = format!("STINE password of {}: ", self.username.value.as_deref().unwrap_or_default());
+        self.password = password::resolve_secret(&self.password, &self.password_command, stdin, interactive, &prompt)?;
+        self.redact_secrets();
+        Ok(())
+    }
+
+    /// Reads the email password like [`Settings::read_password`]
+    pub(crate) fn read_email_password(&mut self, stdin: Option<&mut dyn BufRead>, interactive: bool) -> anyhow::Result<()> {
+        let prompt = format!("Email password of {}: ", self.email_address.value.as_deref().unwrap_or_default());
+        self.email_password = password::resolve_secret(&self.email_password, &self.email_password_command,
+                                                       stdin, interactive, &prompt)?;
+        self.redact_secrets();
+        Ok(())
+    }
+
+    pub(crate) fn uses_store(&self) -> bool {
+        self.encrypted_store.value.unwrap_or_default()
+    }
+
+    /// Uses the passwords of the encrypted store, if they aren't set by another source
+    pub(crate) fn apply_store(&mut self, secrets: &ProfileSecrets) {
+        if self.password.value.is_none() {
+            self.password = Setting::new(None).layer(secrets.password.clone(), Source::Store);
+        }
+        if self.email_password.value.is_none() {
+            self.email_password = Setting::new(None).layer(secrets.email_password.clone(), Source::Store);
+        }
+        self.redact_secrets();
+    }
+
+    /// Masks the passwords and the passphrase in all following log records
+    pub(crate) fn redact_secrets(&self) {
+        for secret in [&self.password, &self.email_password, &self.passphrase] {
+            if let Some(value) = &secret.value {
+                redact::add_secret(value);
+            }
+        }
+    }
+
+    /// Credentials, if both username and password are set
+    pub(crate) fn credentials(&self) -> Option<Credentials> {
+        match (&self.username.value, &self.password.value) {
+            (Some(username), Some(password)) if !username.is_empty() && !password.is_empty() =>
+                Some(Credentials { username: username.clone(), password: password.clone() }),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn config_subcommand() -> Command {
+    Command::new("config")
+        .about("Inspect the configuration. Doesn't require authentication")
+        .subcommand_required(true)
+        .subcommands([
+            Command::new("show")
+                .about("Print all settings of the selected profile and where they come from"),
+            Command::new("encrypt")
+                .about("Move the passwords and sessions of all profiles into a passphrase protected store \
+                and enable it"),
+        ])
+}
+
+/// Row of `config show`
+#[derive(Debug, Serialize)]
+struct SettingEntry {
+    name: &'static str,
+    value: Option<String>,
+    source: String,
+}
+
+impl SettingEntry {
+    fn new<T: Serialize>(name: &'static str, setting: &Setting<T>, secret: bool) -> Self {
+        let value = setting.value.as_ref().map(|value| {
+            if secret {
+                return "********".to_string();
+            }
+            // same representation as in the config file, without quotes of strings
+            serde_json::to_string(value).unwrap_or_default().trim_matches('"').to_string()
+        });
+        SettingEntry { name, value, source: setting.source.to_string() }
+    }
+}
+
+/// Moves all passwords and sessions into the encrypted store and enables it
+fn encrypt(settings: &Settings, config: &mut ConfigFile) -> anyhow::Result<usize> {
+    let passphrase = store::read_passphrase(settings, !STORE_PATH.exists())?;
+    let mut secret_store = SecretStore::open(&STORE_PATH, passphrase)?;
+    let mut sessions: BTreeMap<String, Session> = load_toml(&SESSIONS_PATH)?;
+
+    let moved = store::move_secrets(config, &mut sessions, &mut secret_store);
+    // the store is saved first, so no secret is lost if writing fails
+    secret_store.save()?;
+    config.encrypted_store = true;
+    save_toml(&CONFIG_PATH, config)?;
+    save_toml(&SESSIONS_PATH, &sessions)?;
+
+    Ok(moved)
+}
+
+pub(crate) fn config_command(sub_matches: &ArgMatches, settings: &Settings, config: &mut ConfigFile) {
+    match sub_matches.subcommand() {
+        Some(("show", show_matches)) => {
+            let entries = [
+                SettingEntry::new("profile", &settings.profile, false),
+                SettingEntry::new("username", &settings.username, false),
+                SettingEntry::new("password", &settings.password, true),
+                SettingEntry::new("password_command", &settings.password_command, false),
+                SettingEntry::new("language", &settings.language, false),
+                SettingEntry::new("output", &settings.output, false),
+                SettingEntry::new("cache_dir", &settings.cache_dir, false),
+                SettingEntry::new("notify.dir", &settings.notify_dir, false),
+                SettingEntry::new("notify.events", &settings.notify_events, false),
+                SettingEntry::new("notify.email_address", &settings.email_address, false),
+                SettingEntry::new("notify.email_password", &settings.email_password, true),
+                SettingEntry::new("notify.email_password_command", &settings.email_password_command, false),
+                SettingEntry::new("notify.smtp_server", &settings.smtp_server, false),
+                SettingEntry::new("notify.smtp_port", &settings.smtp_port, false),
+                SettingEntry::new("encrypted_store", &settings.encrypted_store, false),
+                SettingEntry::new("passphrase", &settings.passphrase, true),
+                SettingEntry::new("passphrase_command", &settings.passphrase_command, false),
+                SettingEntry::new("log.path", &settings.log_path, false),
+                SettingEntry::new("log.level", &settings.log_level, false),
+                SettingEntry::new("log.format", &settings.log_format, false),
+                SettingEntry::new("log.targets", &settings.log_targets, false),
+                SettingEntry::new("log.max_size", &settings.log_max_size, false),
+                SettingEntry::new("log.max_age_days", &settings.log_max_age_days, false),
+                SettingEntry::new("log.keep", &settings.log_keep, false),
+            ];
+
+            let format = OutputFormat::from_matches(show_matches);
+            if format.is_table() {
+                println!("{} [{}]", "Config file:".bold(), CONFIG_PATH.display());
+                println!("{} [{}]", "Sessions:".bold(), SESSIONS_PATH.display());
+            }
+
+            let mut table = Table::new();
+            table.add_row(row!["Setting", "Value", "Source"]);
+            for entry in &entries {
+                let source = match entry.source.as_str() {
+                    "default" => entry.source.dimmed(),
+                    _ => entry.source.normal(),
+                };
+                table.add_row(row![entry.name, entry.value.as_deref().unwrap_or("-"), source]);
+            }
+            output::print_output(format, &table, &entries);
+        }
+        Some(("encrypt", _)) => {
+            let moved = encrypt(settings, config)
+                .context("Failed encrypting passwords and sessions").unwrap();
+            println!("{} [{}]", format!("> Moved {moved} passwords and sessions into the encrypted store").bright_green(),
+                     STORE_PATH.display().to_string().underline());
+        }
+        _ => unimplemented!(),
+    }
+}
+
+/// Loads a toml file, or the default value if it doesn't exist
+pub(crate) fn load_toml<T: DeserializeOwned + Default>(path: &Path) -> anyhow::Result<T> {
+    if path.exists() {
+        return Ok(toml::from_str(&fs::read_to_string(path)?)?);
+    }
+    Ok(T::default())
+}
+
+/// Restricts the permissions of `path` to its owner, as config and sessions contain passwords and cookies
+fn restrict_permissions(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Saves `value` as toml, only readable by the owner
+pub(crate) fn save_toml<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    // the mode only applies to new files
+    restrict_permissions(path)?;
+    file.write_all(toml::to_string_pretty(value)?.as_bytes())?;
+    Ok(())
+}
+
+pub(crate) fn save_session(path: &Path, profile: &str, mut session: Session) -> anyhow::Result<()> {
+    let mut sessions: BTreeMap<String, Session> = load_toml(path)?;
+    session.last_used = Some(Utc::now().timestamp());
+    sessions.insert(profile.to_string(), session);
+    save_toml(path, &sessions)
+}
+
+/// Moves credentials and session of an old `.stine-env` into the [`DEFAULT_PROFILE`].
+/// Only migrates if there is no config file yet. The plaintext `.stine-env` is renamed to `.stine-env.bak`,
+/// which is only readable by the owner. Returns the path of the backup, if a config was migrated
+pub(crate) fn migrate_legacy_config(legacy_path: &Path, config_path: &Path, sessions_path: &Path)
+                                    -> anyhow::Result<Option<PathBuf>> {
+    if config_path.exists() || !legacy_path.exists() {
+        return Ok(None);
+    }
+
+    let legacy: LegacyConfig = load_toml(legacy_path)?;
+    info!("Migrating {} to {}", legacy_path.display(), config_path.display());
+
+    let profile = Profile {
+        username: Some(legacy.username).filter(|u| !u.is_empty()),
+        password: Some(legacy.password).filter(|p| !p.is_empty()),
+        ..Profile::default()
+    };
+    let config = ConfigFile {
+        profile: BTreeMap::from([(DEFAULT_PROFILE.to_string(), profile)]),
+        ..ConfigFile::default()
+    };
+    save_toml(config_path, &config)?;
+
+    if !legacy.session.is_empty() {
+        let mut sessions: BTreeMap<String, Session> = load_toml(sessions_path)?;
+        sessions.insert(DEFAULT_PROFILE.to_string(), Session {
+            session: legacy.session,
+            cnsc_cookie: legacy.cnsc_cookie,
+            last_used: legacy.last_used,
+        });
+        save_toml(sessions_path, &sessions)?;
+    }
+
+    let mut backup_name = legacy_path.file_name().unwrap_or_default().to_os_string();
+    backup_name.push(".bak");
+    let backup_path = legacy_path.with_file_name(backup_name);
+    fs::rename(legacy_path, &backup_path)?;
+    restrict_permissions(&backup_path)?;
+
+    Ok(Some(backup_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::config::{ConfigFile, DEFAULT_PROFILE, load_toml, migrate_legacy_config, Session, Settings, Source,
+                        xdg_config_dir};
+    use crate::{get_command, Language};
+    use crate::logging::LogFormat;
+    use crate::notify::NotifyEvent;
+    use crate::output::OutputFormat;
+
+    #[test]
+    fn test_xdg_config_dir() {
+        let home = Some(PathBuf::from("/home/user"));
+        assert_eq!(xdg_config_dir(Some("/tmp/config".into()), home.clone()), PathBuf::from("/tmp/config"));
+        assert_eq!(xdg_config_dir(None, home.clone()), PathBuf::from("/home/user/.config"));
+        // relative paths are invalid according to the spec
+        assert_eq!(xdg_config_dir(Some("config".into()), home.clone()), PathBuf::from("/home/user/.config"));
+        assert_eq!(xdg_config_dir(Some("".into()), home), PathBuf::from("/home/user/.config"));
+    }
+
+    #[test]
+    fn test_parse_profiles() {
+        let config: ConfigFile = toml::from_str(r#"
+            default_profile = "work"
+
+            [profile.default]
+            username = "baa1234"
+
+            [profile.work]
+            username = "bab5678"
+            language = "english"
+            output = "json"
+
+            [profile.work.notify]
+            events = ["exam-result", "documents"]
+            email_address = "notify@example.com"
+        "#).unwrap();
+
+        let work = config.get_profile("work");
+        assert_eq!(work.username.as_deref(), Some("bab5678"));
+        assert_eq!(work.language, Some(Language::English));
+        assert_eq!(work.output, Some(OutputFormat::Json));
+        assert!(matches!(work.notify.events[..], [NotifyEvent::ExamResult, NotifyEvent::Documents]));
+        assert_eq!(work.notify.smtp_port, None);
+
+        assert_eq!(config.get_profile("missing"), Default::default());
+    }
+
+    #[test]
+    fn test_resolve_settings() {
+        let config: ConfigFile = toml::from_str(r#"
+            default_profile = "work"
+
+            [log]
+            format = "json"
+            max_size = "1M"
+
+            [profile.work]
+            username = "bab5678"
+            password = "hunter2"
+            language = "german"
+
+            [profile.work.notify]
+            smtp_port = 465
+        "#).unwrap();
+        let env = |name: &str| match name {
+            "STINE_USERNAME" => Some("env-user".to_string()),
+            "STINE_LANGUAGE" => Some("English".to_string()),
+            "STINE_NOTIFY_EVENTS" => Some("exam-result, documents".to_string()),
+            "STINE_SMTP_PORT" => Some("587".to_string()),
+            "STINE_LOG_KEEP" => Some("5".to_string()),
+            "STINE_CACHE_DIR" => Some("/tmp/env-cache".to_string()),
+            "STINE_NOTIFY_DIR" => Some("/tmp/env-notify".to_string()),
+            _ => None,
+        };
+
+        let matches = get_command().try_get_matches_from(
+            ["stine-cli", "--username", "flag-user", "--cache-dir", "/tmp/flag-cache",
+                "notify", "--smtp_port", "25", "--notify-dir", "/tmp/flag-notify"]).unwrap();
+        let settings = Settings::resolve(&matches, &config, env).unwrap();
+
+        assert_eq!(settings.profile_name(), "work");
+        assert_eq!(settings.profile.source, Source::ConfigFile);
+        assert_eq!(settings.username.value.as_deref(), Some("flag-user"));
+        assert_eq!(settings.username.source, Source::Flag("--username"));
+        assert_eq!(settings.password.source, Source::ConfigFile);
+        assert_eq!(settings.language.value, Some(Language::English));
+        assert_eq!(settings.language.source, Source::Env("STINE_LANGUAGE"));
+        assert_eq!(settings.output.source, Source::Default);
+        assert!(matches!(settings.notify_events.value.as_deref(), Some([NotifyEvent::ExamResult, NotifyEvent::Documents])));
+        assert_eq!(settings.smtp_port.value, Some(25));
+        assert_eq!(settings.email_password.value, None);
+        assert!(settings.credentials().is_some());
+        assert_eq!(settings.log_format.value, Some(LogFormat::Json));
+        assert_eq!(settings.log_max_size.value, Some(1024 * 1024));
+        assert_eq!(settings.log_keep.value, Some(5));
+        assert_eq!(settings.log_level.source, Source::Default);
+        assert_eq!(settings.cache_dir.value, Some(PathBuf::from("/tmp/flag-cache")));
+        assert_eq!(settings.cache_dir.source, Source::Flag("--cache-dir"));
+        assert_eq!(settings.notify_dir.value, Some(PathBuf::from("/tmp/flag-notify")));
+        assert_eq!(settings.notify_dir.source, Source::Flag("--notify-dir"));
+
+        // profile of the flag has no settings
+        let matches = get_command().try_get_matches_from(["stine-cli", "--profile", "other", "check"]).unwrap();
+        let settings = Settings::resolve(&matches, &config, |_| None).unwrap();
+        assert_eq!(settings.profile_name(), "other");
+        assert_eq!(settings.username.value, None);
+        assert!(settings.credentials().is_none());
+
+        let invalid_env = |name: &str| (name == "STINE_SMTP_PORT").then(|| "port".to_string());
+        assert!(Settings::resolve(&matches, &config, invalid_env).is_err());
+    }
+
+    #[test]
+    fn test_migrate_legacy_config() {
+        let dir = std::env::temp_dir().join("stine-cli-config-test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+
+        let legacy_path = dir.join(".stine-env");
+        let config_path = dir.join("config").join("config.toml");
+        let sessions_path = dir.join("sessions.toml");
+        fs::write(&legacy_path, r#"
+            username = "baa1234"
+            password = "hunter2"
+            session = "123456789"
+            cnsc_cookie = "abc"
+            last_used = 1700000000
+        "#).unwrap();
+
+        let backup_path = migrate_legacy_config(&legacy_path, &config_path, &sessions_path).unwrap();
+        assert_eq!(backup_path, Some(dir.join(".stine-env.bak")));
+        assert!(!legacy_path.exists());
+        // config exists now
+        fs::write(&legacy_path, "").unwrap();
+        assert_eq!(migrate_legacy_config(&legacy_path, &config_path, &sessions_path).unwrap(), None);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            for path in [&config_path, &sessions_path, &dir.join(".stine-env.bak")] {
+                assert_eq!(fs::metadata(path).unwrap().permissions().mode() & 0o777, 0o600);
+            }
+        }
+
+        let config: ConfigFile = load_toml(&config_path).unwrap();
+        let profile = config.get_profile(DEFAULT_PROFILE);
+        assert_eq!(profile.username.as_deref(), Some("baa1234"));
+        assert_eq!(profile.password.as_deref(), Some("hunter2"));
+
+        let sessions: BTreeMap<String, Session> = load_toml(&sessions_path).unwrap();
+        assert_eq!(sessions[DEFAULT_PROFILE].session, "123456789");
+        assert_eq!(sessions[DEFAULT_PROFILE].last_used, Some(1700000000));
+    }
+}
@@ -0,0 +1,542 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use chrono::{DateTime, Local, Utc};
+use clap::{arg, ArgAction, ArgMatches, Command, value_parser};
+use colored::Colorize;
+use prettytable::{row, Table};
+use serde::Serialize;
+
+use stine_rs::{Appointment, CourseInfo, Exam, LazyLevel, Module, ModuleCategory, Stine, SubModule};
+
+use crate::colorize_event_type;
+use crate::output;
+use crate::output::OutputFormat;
+
+pub(crate) fn courses_subcommand() -> Command {
+    Command::new("courses")
+        .about("Print all available courses as a tree of categories, modules and submodules")
+        .arg(arg!(-c --category <CATEGORY> "Only print categories containing this name (case-insensitive)")
+            .required(false)
+            .value_parser(value_parser!(String)))
+        .arg(arg!(-i --info "Load and print the info of every submodule, like event type and instructors. \
+            Requires an extra request for every submodule")
+            .required(false)
+            .action(ArgAction::SetTrue))
+        .arg(arg!(-f --"force-refresh" "Scrape all courses again instead of using the cache. Takes several minutes")
+            .required(false)
+            .action(ArgAction::SetTrue))
+}
+
+fn format_submodule(submodule: &mut SubModule, stine: &Stine, load_info: bool) -> String {
+    if !load_info {
+        return submodule.name.clone();
+    }
+
+    let info = submodule.info(stine);
+    let name = colorize_event_type(submodule.name.clone(), info.event_type).to_string();
+    match info.instructors {
+        Some(instructors) if !instructors.is_empty() =>
+            format!("{name} {}", format!("({})", instructors.join(", ")).dimmed()),
+        _ => name,
+    }
+}
+
+fn print_tree(categories: Vec<ModuleCategory>, stine: &Stine, load_info: bool) {
+    for category in categories {
+        println!("{}", category.name.bold());
+
+        let module_count = category.modules.len();
+        let orphan_count = category.orphan_submodules.len();
+
+        for (i, module) in category.modules.into_iter().enumerate() {
+            let last_module = i + 1 == module_count && orphan_count == 0;
+            let (branch, indent) = if last_module { ("└──", "    ") } else { ("├──", "│   ") };
+            println!("{branch} {}", module.to_string().bright_blue());
+
+            let submodule_count = module.sub_modules.len();
+            for (j, mut submodule) in module.sub_modules.into_iter().enumerate() {
+                let branch = if j + 1 == submodule_count { "└──" } else { "├──" };
+                println!("{indent}{branch} {}", format_submodule(&mut submodule, stine, load_info));
+            }
+        }
+
+        for (i, mut submodule) in category.orphan_submodules.into_iter().enumerate() {
+            let branch = if i + 1 == orphan_count { "└──" } else { "├──" };
+            println!("{branch} {}", format_submodule(&mut submodule, stine, load_info));
+        }
+        println!();
+    }
+}
+
+/// One row per submodule, for formats which can't display trees
+fn flat_table(categories: &[ModuleCategory]) -> Table {
+    let mut table = Table::new();
+    table.add_row(row!["Category", "Module", "Course", "Id"]);
+    for category in categories {
+        for module in &category.modules {
+            for submodule in &module.sub_modules {
+                table.add_row(row![category.name, module.to_string(), submodule.name, submodule.id]);
+            }
+        }
+        for submodule in &category.orphan_submodules {
+            table.add_row(row![category.name, "", submodule.name, submodule.id]);
+        }
+    }
+    table
+}
+
+pub(crate) fn courses_command(sub_matches: &ArgMatches, stine: &mut Stine) {
+    let force_refresh = sub_matches.get_flag("force-refresh");
+    let load_info = sub_matches.get_flag("info");
+    let category_filter = sub_matches.get_one::<String>("category").map(|c| c.to_lowercase());
+
+    let categories = if force_refresh {
+        eprintln!("Scraping all courses. This will take a few minutes");
+        stine.get_registration_modules(true, true, LazyLevel::FullLazy)
+    } else {
+        let mut spinner = output::spinner("Loading courses");
+        let categories = stine.get_registration_modules(false, false, LazyLevel::FullLazy);
+        output::stop_spinner(&mut spinner);
+        categories
+    }.context("Failed loading courses. If there is no cache yet, use --force-refresh").unwrap();
+
+    let categories: Vec<ModuleCategory> = categories.into_iter()
+        .filter(|c| match &category_filter {
+            Some(filter) => c.name.to_lowercase().contains(filter),
+            None => true,
+        })
+        .collect();
+
+    if categories.is_empty() {
+        println!("{}", "No matching categories found".bright_red());
+        return;
+    }
+
+    let format = OutputFormat::from_matches(sub_matches);
+    if format.is_table() {
+        print_tree(categories, stine, load_info);
+    } else {
+        output::print_output(format, &flat_table(&categories), &categories);
+    }
+}
+
+pub(crate) fn course_subcommand() -> Command {
+    Command::new("course")
+        .about("Inspect a single course or module")
+        .subcommand_required(true)
+        .subcommands([
+            Command::new("show")
+                .about("Print all info about a course or module. \
+                Scrapes all courses if it can't be found in the cache, which takes several minutes")
+                .arg(arg!(<IDENT> "Course number (64-074), module number (InfB-SE 1) or id of a course")
+                    .value_parser(value_parser!(String))),
+        ])
+}
+
+enum Course {
+    Module(Box<Module>),
+    SubModule(Box<SubModule>),
+}
+
+/// Searches the course in the cache. Ids only consist of digits
+fn find_course(stine: &mut Stine, ident: &str) -> Option<Course> {
+    if ident.chars().all(|c| c.is_ascii_digit()) {
+        return stine.get_submodule_by_id(ident.to_string(), false, LazyLevel::FullLazy).ok()
+            .map(|s| Course::SubModule(Box::new(s.clone())));
+    }
+
+    if let Ok(submodule) = stine.get_submodule_by_course_number(ident, false, LazyLevel::FullLazy) {
+        return Some(Course::SubModule(Box::new(submodule.clone())));
+    }
+
+    stine.get_module_by_number(ident.to_string(), false, LazyLevel::FullLazy).ok()
+        .map(|m| Course::Module(Box::new(m.clone())))
+}
+
+fn format_datetime(datetime: Option<DateTime<Utc>>) -> String {
+    datetime.map_or_else(|| "-".to_string(),
+                         |dt| DateTime::<Local>::from(dt).format("%a %Y-%m-%d %H:%M").to_string())
+}
+
+fn print_field<T: ToString>(name: &str, value: Option<T>) {
+    if let Some(value) = value {
+        println!("{} {}", format!("{name}:").bold(), value.to_string());
+    }
+}
+
+fn print_attributes(attributes: &HashMap<String, String>) {
+    if attributes.is_empty() {
+        return;
+    }
+
+    println!("\n{}", "Attributes".bold().underline());
+    let mut keys: Vec<&String> = attributes.keys().collect();
+    keys.sort();
+    for key in keys {
+        print_field(key, Some(&attributes[key]));
+    }
+}
+
+fn appointments_table(appointments: &[Appointment]) -> Table {
+    let mut table = Table::new();
+    table.add_row(row!["From", "To", "Room", "Instructors"]);
+    for appointment in appointments {
+        table.add_row(row![
+            format_datetime(appointment.from),
+            format_datetime(appointment.to),
+            appointment.room,
+            appointment.instructors.join(", "),
+        ]);
+    }
+    table
+}
+
+/// Group of a course with its loaded appointments
+#[derive(Serialize)]
+struct GroupView {
+    name: String,
+    instructors: Vec<String>,
+    schedule: String,
+    appointments: Vec<Appointment>,
+}
+
+/// Module of a course, whose exams and credits apply to the course
+#[derive(Serialize)]
+struct ParentModuleView {
+    module_number: String,
+    name: String,
+    credits: Option<String>,
+    exams: Vec<Exam>,
+}
+
+/// Course with its lazily loaded info, appointments and groups
+#[derive(Serialize)]
+struct CourseView {
+    id: String,
+    course_number: String,
+    name: String,
+    info: CourseInfo,
+    appointments: Vec<Appointment>,
+    groups: Vec<GroupView>,
+    module: Option<ParentModuleView>,
+}
+
+impl CourseView {
+    /// Loads the info, appointments and groups of the submodule
+    fn load(mut submodule: SubModule, module: Option<&Module>, stine: &Stine) -> Self {
+        let info = submodule.info(stine);
+        let appointments = submodule.appointments(stine).unwrap_or_default();
+        let groups = submodule.groups(stine).unwrap_or_default().into_iter()
+            .map(|mut group| GroupView {
+                appointments: group.get_appointments(stine),
+                name: group.name,
+                instructors: group.instructors,
+                schedule: group.schedule_str,
+            })
+            .collect();
+
+        Self {
+            id: submodule.id,
+            course_number: submodule.course_number,
+            name: submodule.name,
+            info,
+            appointments,
+            groups,
+            module: module.map(|module| ParentModuleView {
+                module_number: module.module_number.clone(),
+                name: module.name.clone(),
+                credits: module.credits.clone(),
+                exams: module.exams.clone(),
+            }),
+        }
+    }
+}
+
+/// Course of a [`ModuleView`], without the lazily loaded parts
+#[derive(Serialize)]
+struct ModuleCourseView {
+    id: String,
+    course_number: String,
+    name: String,
+}
+
+/// Module whose courses are listed by id and name
+#[derive(Serialize)]
+struct ModuleView {
+    module_number: String,
+    name: String,
+    owner: String,
+    timetable_name: Option<String>,
+    duration: Option<i32>,
+    electives: Option<i32>,
+    credits: Option<String>,
+    start_semester: Option<String>,
+    exams: Vec<Exam>,
+    courses: Vec<ModuleCourseView>,
+    attributes: HashMap<String, String>,
+}
+
+impl From<&Module> for ModuleView {
+    fn from(module: &Module) -> Self {
+        Self {
+            module_number: module.module_number.clone(),
+            name: module.name.clone(),
+            owner: module.owner.clone(),
+            timetable_name: module.timetable_name.clone(),
+            duration: module.duration,
+            electives: module.electives,
+            credits: module.credits.clone(),
+            start_semester: module.start_semester.clone(),
+            exams: module.exams.clone(),
+            courses: module.sub_modules.iter()
+                .map(|submodule| ModuleCourseView {
+                    id: submodule.id.clone(),
+                    course_number: submodule.course_number.clone(),
+                    name: submodule.name.clone(),
+                })
+                .collect(),
+            attributes: module.attributes.clone(),
+        }
+    }
+}
+
+fn format_appointment(appointment: &Appointment) -> String {
+    format!("{} - {} {}", format_datetime(appointment.from), format_datetime(appointment.to), appointment.room)
+}
+
+fn format_exam(exam: &Exam) -> String {
+    format!("{} {} - {}", exam.name, format_datetime(exam.datetime_from), format_datetime(exam.datetime_to))
+}
+
+/// Adds a row for `value`, if it is present
+fn add_field<T: ToString>(table: &mut Table, name: &str, value: Option<T>) {
+    if let Some(value) = value {
+        table.add_row(row![name, value.to_string()]);
+    }
+}
+
+/// One row per field, for formats which can't display sections
+fn course_table(course: &CourseView) -> Table {
+    let info = &course.info;
+    let mut table = Table::new();
+    table.add_row(row!["Field", "Value"]);
+    add_field(&mut table, "Name", Some(&course.name));
+    add_field(&mut table, "Id", Some(&course.id));
+    add_field(&mut table, "Event type", info.event_type.map(|e| format!("{e:?}")).or(info.event_type_raw.clone()));
+    add_field(&mut table, "Instructors", info.instructors.as_ref().map(|i| i.join(", ")));
+    add_field(&mut table, "Timetable name", info.timetable_name.as_ref());
+    add_field(&mut table, "Hours per week", info.hours_per_week);
+    add_field(&mut table, "Credits", info.credits.as_ref());
+    add_field(&mut table, "Language", info.language.as_ref());
+    add_field(&mut table, "Min. participants", info.min_participants);
+    add_field(&mut table, "Max. participants", info.max_participants);
+    for appointment in &course.appointments {
+        table.add_row(row!["Appointment", format_appointment(appointment)]);
+    }
+    for group in &course.groups {
+        for appointment in &group.appointments {
+            table.add_row(row![format!("Group {}", group.name), format_appointment(appointment)]);
+        }
+    }
+    if let Some(module) = &course.module {
+        add_field(&mut table, "Module", Some(format!("{} - {}", module.module_number, module.name)));
+        add_field(&mut table, "Module credits", module.credits.as_ref());
+        for exam in &module.exams {
+            table.add_row(row!["Exam", format_exam(exam)]);
+        }
+    }
+    table
+}
+
+/// One row per field, for formats which can't display sections
+fn module_table(module: &ModuleView) -> Table {
+    let mut table = Table::new();
+    table.add_row(row!["Field", "Value"]);
+    add_field(&mut table, "Module", Some(format!("{} - {}", module.module_number, module.name)));
+    add_field(&mut table, "Owner", Some(&module.owner).filter(|o| !o.is_empty()));
+    add_field(&mut table, "Timetable name", module.timetable_name.as_ref());
+    add_field(&mut table, "Duration", module.duration);
+    add_field(&mut table, "Electives", module.electives);
+    add_field(&mut table, "Credits", module.credits.as_ref());
+    add_field(&mut table, "Start semester", module.start_semester.as_ref());
+    for exam in &module.exams {
+        table.add_row(row!["Exam", format_exam(exam)]);
+    }
+    for course in &module.courses {
+        table.add_row(row!["Course", format!("{} [{}]", course.name, course.id)]);
+    }
+    table
+}
+
+fn print_submodule(course: &CourseView) {
+    let info = &course.info;
+
+    println!("{}", colorize_event_type(course.name.clone(), info.event_type).bold());
+    print_field("Id", Some(&course.id));
+    print_field("Event type", info.event_type.map(|e| format!("{e:?}")).or(info.event_type_raw.clone()));
+    print_field("Instructors", info.instructors.as_ref().map(|i| i.join(", ")));
+    print_field("Timetable name", info.timetable_name.as_ref());
+    print_field("Hours per week", info.hours_per_week);
+    print_field("Credits", info.credits.as_ref());
+    print_field("Language", info.language.as_ref());
+    print_field("Min. participants", info.min_participants);
+    print_field("Max. participants", info.max_participants);
+
+    if !course.appointments.is_empty() {
+        println!("\n{}", "Appointments".bold().underline());
+        appointments_table(&course.appointments).printstd();
+    }
+
+    for group in &course.groups {
+        println!("\n{} {}", "Group".bold().underline(), group.name.bold());
+        print_field("Instructors", Some(group.instructors.join(", ")).filter(|i| !i.is_empty()));
+        print_field("Schedule", Some(&group.schedule).filter(|s| !s.is_empty()));
+        appointments_table(&group.appointments).printstd();
+    }
+
+    print_attributes(&info.attributes.clone().unwrap_or_default());
+
+    if let Some(module) = &course.module {
+        print_parent_module(module);
+    }
+}
+
+fn print_exams(exams: &[Exam]) {
+    if exams.is_empty() {
+        return;
+    }
+
+    println!("\n{}", "Exams".bold().underline());
+    let mut table = Table::new();
+    table.add_row(row!["Name", "From", "To", "Instructors", "Mandatory"]);
+    for exam in exams {
+        table.add_row(row![
+            exam.name,
+            format_datetime(exam.datetime_from),
+            format_datetime(exam.datetime_to),
+            exam.instructors.join(", "),
+            exam.is_mandatory.map_or_else(|| exam.is_mandatory_raw.clone(), |m| m.to_string()),
+        ]);
+    }
+    table.printstd();
+}
+
+/// Prints the module of a course, whose exams and credits apply to the course
+fn print_parent_module(module: &ParentModuleView) {
+    println!("\n{} {}", "Module".bold().underline(),
+             format!("{} - {}", module.module_number, module.name).bold());
+    print_field("Credits", module.credits.as_ref());
+    print_exams(&module.exams);
+}
+
+fn print_module(module: Module) {
+    println!("{}", module.to_string().bold());
+    print_field("Owner", Some(&module.owner).filter(|o| !o.is_empty()));
+    print_field("Timetable name", module.timetable_name.as_ref());
+    print_field("Duration", module.duration);
+    print_field("Electives", module.electives);
+    print_field("Credits", module.credits.as_ref());
+    print_field("Start semester", module.start_semester.as_ref());
+
+    print_exams(&module.exams);
+
+    if !module.sub_modules.is_empty() {
+        println!("\n{}", "Courses".bold().underline());
+        for submodule in &module.sub_modules {
+            println!("  {} [{}]", submodule.name, submodule.id);
+        }
+    }
+
+    print_attributes(&module.attributes);
+}
+
+pub(crate) fn course_command(sub_matches: &ArgMatches, stine: &mut Stine) {
+    match sub_matches.subcommand() {
+        Some(("show", show_matches)) => {
+            let ident: &String = show_matches.get_one("IDENT").unwrap();
+
+            let course = find_course(stine, ident).or_else(|| {
+                eprintln!("'{ident}' not found in the cache. Scraping all courses, this will take a few minutes");
+                stine.get_registration_modules(true, true, LazyLevel::FullLazy)
+                    .context("Failed scraping courses").unwrap();
+                find_course(stine, ident)
+            });
+
+            let format = OutputFormat::from_matches(show_matches);
+            match course {
+                Some(Course::SubModule(submodule)) => {
+                    let module = stine.get_module_of_submodule(&submodule.id).ok().cloned();
+                    let course = CourseView::load(*submodule, module.as_ref(), stine);
+                    if format.is_table() {
+                        print_submodule(&course);
+                    } else {
+                        output::print_output(format, &course_table(&course), &course);
+                    }
+                }
+                Some(Course::Module(module)) => {
+                    if format.is_table() {
+                        print_module(*module);
+                    } else {
+                        let module = ModuleView::from(module.as_ref());
+                        output::print_output(format, &module_table(&module), &module);
+                    }
+                }
+                None => println!("{}", format!("No course or module '{ident}' found").bright_red()),
+            }
+        }
+        _ => unimplemented!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use stine_rs::{CourseInfo, Exam};
+
+    use crate::courses::{course_table, CourseView, ParentModuleView};
+
+    #[test]
+    fn test_course_table() {
+        let course = CourseView {
+            id: "1".to_string(),
+            course_number: "64-074".to_string(),
+            name: "64-074 Softwareentwicklung".to_string(),
+            info: CourseInfo {
+                credits: Some("6".to_string()),
+                attributes: Some(HashMap::new()),
+                ..Default::default()
+            },
+            appointments: vec![],
+            groups: vec![],
+            module: Some(ParentModuleView {
+                module_number: "InfB-SE 1".to_string(),
+                name: "Softwareentwicklung I".to_string(),
+                credits: Some("9,0".to_string()),
+                exams: vec![Exam {
+                    name: "Klausur".to_string(),
+                    datetime_from: None,
+                    datetime_to: None,
+                    instructors: vec![],
+                    is_mandatory: Some(true),
+                    is_mandatory_raw: "ja".to_string(),
+                }],
+            }),
+        };
+
+        let rows: Vec<Vec<String>> = course_table(&course).row_iter()
+            .map(|row| row.iter().map(|cell| cell.get_content()).collect())
+            .collect();
+        assert_eq!(rows[0], ["Field", "Value"]);
+        assert!(rows.contains(&vec!["Credits".to_string(), "6".to_string()]));
+        assert!(rows.contains(&vec!["Module".to_string(), "InfB-SE 1 - Softwareentwicklung I".to_string()]));
+        assert!(rows.contains(&vec!["Module credits".to_string(), "9,0".to_string()]));
+        assert!(rows.contains(&vec!["Exam".to_string(), "Klausur - - -".to_string()]));
+
+        // the loaded info instead of the lazy status
+        let json = serde_json::to_value(&course).unwrap();
+        assert_eq!(json["info"]["credits"], "6");
+        assert_eq!(json["module"]["exams"][0]["name"], "Klausur");
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::Context;
+use clap::{arg, ArgMatches, Command, value_parser};
+use colored::Colorize;
+use prettytable::{row, Table};
+
+use stine_rs::mobile::cipher::decrypt_arguments;
+
+use crate::output;
+use crate::output::OutputFormat;
+
+pub(crate) fn debug_subcommand() -> Command {
+    Command::new("debug")
+        .about("Tools for inspecting the STINE api. Don't require authentication")
+        .subcommand_required(true)
+        .subcommands([
+            Command::new("decode-args")
+                .about("Decrypt the -A argument of a captured request of the STINE mobile app")
+                .arg(arg!(<URL> "Captured url, its ARGUMENTS or only the encrypted argument")
+                    // the bare argument starts with -A
+                    .allow_hyphen_values(true)
+                    .value_parser(value_parser!(String))),
+        ])
+}
+
+/// Returns the encrypted argument of an ACTIONMOBILE url,
+/// e.g. `...&PRGNAME=ACTIONMOBILE&ARGUMENTS=-A<encrypted>`
+fn extract_encrypted_argument(url: &str) -> &str {
+    let args = url.split("ARGUMENTS=").nth(1).unwrap_or(url);
+    // other url parameters may follow
+    args.split('&').next().unwrap_or_default()
+}
+
+pub(crate) fn debug_command(sub_matches: &ArgMatches) -> anyhow::Result<()> {
+    match sub_matches.subcommand() {
+        Some(("decode-args", decode_matches)) => {
+            let url: &String = decode_matches.get_one("URL").unwrap();
+
+            let decrypted = decrypt_arguments(extract_encrypted_argument(url))
+                .context("Failed decrypting arguments")?;
+
+            let format = OutputFormat::from_matches(decode_matches);
+            if format.is_table() {
+                println!("{} {}", "PRGNAME:".bold(), decrypted.prg_name);
+                println!("{} {}", "Session:".bold(), decrypted.session_id);
+            }
+
+            let mut table = Table::new();
+            table.add_row(row!["#", "Argument"]);
+            for (i, argument) in decrypted.arguments.iter().enumerate() {
+                table.add_row(row![i, argument]);
+            }
+            output::print_output(format, &table, &decrypted);
+        }
+        _ => unimplemented!(),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::debug::extract_encrypted_argument;
+
+    #[test]
+    fn test_extract_encrypted_argument() {
+        let url = "https://www.stine.uni-hamburg.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=ACTIONMOBILE&ARGUMENTS=-Aabc~_";
+        assert_eq!(extract_encrypted_argument(url), "-Aabc~_");
+        assert_eq!(extract_encrypted_argument("-Aabc~_"), "-Aabc~_");
+        assert_eq!(extract_encrypted_argument("ARGUMENTS=-Aabc&foo=bar"), "-Aabc");
+    }
+}
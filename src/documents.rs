@@ -0,0 +1,307 @@
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use chrono::{DateTime, Local, Utc};
+use clap::{arg, ArgMatches, Command, value_parser};
+use colored::Colorize;
+use lazy_static::lazy_static;
+use log::{error, info};
+use prettytable::{row, Table};
+use serde::{Deserialize, Serialize};
+
+use stine_rs::{Document, DownloadedDocument, Stine};
+
+use crate::materials::sanitize_file_name;
+use crate::output;
+use crate::output::OutputFormat;
+
+lazy_static! {
+    static ref DOCUMENTS_PATH: PathBuf = dirs::data_dir().unwrap().join("stine-cli").join("documents");
+}
+
+const INDEX_FILE: &str = "index.json";
+
+/// Entry of the archive index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ArchivedDocument {
+    pub name: String,
+    pub datetime: DateTime<Utc>,
+    pub status: Option<String>,
+    /// Hex encoded SHA-256 hash of the file
+    pub sha256: String,
+    /// Path of the file, relative to the archive directory
+    pub path: PathBuf,
+    pub downloaded: DateTime<Utc>,
+}
+
+pub(crate) fn documents_subcommand() -> Command {
+    Command::new("documents")
+        .about("List, download and archive your STINE documents, like enrollment certificates")
+        .subcommand_required(true)
+        .subcommands([
+            Command::new("list")
+                .about("List the documents currently available in STINE")
+                .arg(output::json_flag()),
+            Command::new("get")
+                .about("Download a document")
+                .arg(arg!(<DOCUMENT> "Name or index of the document. See `documents list`")
+                    .value_parser(value_parser!(String)))
+                .arg(arg!(-f --file <PATH> "File or directory to save the document to. \
+                    Defaults to the file name sent by STINE in the current directory")
+                    .required(false)
+                    .value_parser(value_parser!(PathBuf))),
+            Command::new("sync")
+                .about("Download new documents into the archive. Documents with a known name and date are skipped, \
+                files are never overwritten")
+                .arg(arg!(-d --dir <DIR> "Directory of the archive")
+                    .required(false)
+                    .default_value(DOCUMENTS_PATH.as_os_str())
+                    .value_parser(value_parser!(PathBuf))),
+        ])
+}
+
+fn load_index(dir: &Path) -> anyhow::Result<Vec<ArchivedDocument>> {
+    let index_path = dir.join(INDEX_FILE);
+    if !index_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&index_path)?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed parsing archive index. Consider fixing or deleting {}", index_path.display()))
+}
+
+fn save_index(dir: &Path, index: &[ArchivedDocument]) -> anyhow::Result<()> {
+    fs::write(dir.join(INDEX_FILE), serde_json::to_string_pretty(index)?)?;
+    Ok(())
+}
+
+/// Appends a counter to the file name, until no file exists at the path
+/// Example: Bescheinigung.pdf -> Bescheinigung (1).pdf
+fn unique_path(path: PathBuf) -> PathBuf {
+    if !path.exists() {
+        return path;
+    }
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let extension = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+
+    (1..).map(|i| path.with_file_name(format!("{stem} ({i}){extension}")))
+        .find(|p| !p.exists())
+        .unwrap()
+}
+
+/// Downloads the document into a directory named after its date.
+/// Returns `None` without downloading if the archive contains a document with the same name and date,
+/// or after downloading if it contains a file with the same content
+fn archive_document(dir: &Path, index: &[ArchivedDocument], document: &Document,
+                    download: impl FnOnce(&Document) -> anyhow::Result<DownloadedDocument>)
+                    -> anyhow::Result<Option<ArchivedDocument>> {
+    // STINE may generate the same document with different bytes, e.g. with an embedded timestamp
+    if index.iter().any(|d| d.name == document.name && d.datetime == document.datetime) {
+        return Ok(None);
+    }
+
+    let downloaded = download(document)?;
+    if index.iter().any(|d| d.sha256 == downloaded.metadata.sha256) {
+        return Ok(None);
+    }
+
+    let date_dir = DateTime::<Local>::from(document.datetime).format("%Y-%m-%d").to_string();
+    fs::create_dir_all(dir.join(&date_dir))?;
+
+    let file_name = downloaded.metadata.file_name
+        .unwrap_or_else(|| format!("{}.pdf", document.name));
+    let path = unique_path(dir.join(&date_dir).join(sanitize_file_name(&file_name)));
+
+    // create_new: never overwrite a file
+    OpenOptions::new().write(true).create_new(true).open(&path)?
+        .write_all(&downloaded.content)?;
+
+    Ok(Some(ArchivedDocument {
+        name: document.name.clone(),
+        datetime: document.datetime,
+        status: document.status.clone(),
+        sha256: downloaded.metadata.sha256,
+        path: path.strip_prefix(dir)?.to_path_buf(),
+        downloaded: Utc::now(),
+    }))
+}
+
+fn sync(stine: &Stine, dir: &Path) {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed creating directory {}", dir.display())).unwrap();
+    let mut index = load_index(dir).unwrap();
+
+    let documents = fetch_documents(stine);
+
+    let (mut archived, mut skipped, mut failed) = (0, 0, 0);
+    for document in documents {
+        match archive_document(dir, &index, &document, |document| stine.download_document(document)) {
+            Ok(Some(entry)) => {
+                println!("{} {}", "Archived".green(), entry.path.display());
+                index.push(entry);
+                archived += 1;
+            }
+            Ok(None) => {
+                info!("Skipping already archived document: {}", document.name);
+                skipped += 1;
+            }
+            Err(err) => {
+                error!("Failed archiving document {}: {err}", document.name);
+                eprintln!("{} {}: {err}", "Failed archiving".red(), document.name);
+                failed += 1;
+            }
+        }
+    }
+
+    save_index(dir, &index)
+        .with_context(|| format!("Failed saving archive index to {}", dir.display())).unwrap();
+
+    println!("{} archived, {} already archived, {} failed [{}]",
+             archived.to_string().bright_green(), skipped, failed.to_string().red(),
+             dir.display().to_string().underline());
+}
+
+fn fetch_documents(stine: &Stine) -> Vec<Document> {
+    let mut spinner = output::spinner("Fetching documents");
+    let documents = stine.get_documents()
+        .context("Failed fetching documents").unwrap();
+    output::stop_spinner(&mut spinner);
+
+    documents
+}
+
+/// Finds a document by its index or name. Names are compared case-insensitive
+fn find_document<'a>(documents: &'a [Document], ident: &str) -> Option<&'a Document> {
+    if let Ok(index) = ident.parse::<usize>() {
+        return documents.get(index);
+    }
+
+    let ident = ident.to_lowercase();
+    documents.iter().find(|d| d.name.to_lowercase() == ident)
+        .or_else(|| documents.iter().find(|d| d.name.to_lowercase().contains(&ident)))
+}
+
+fn get(stine: &Stine, ident: &str, file: Option<&PathBuf>) {
+    let documents = fetch_documents(stine);
+    let Some(document) = find_document(&documents, ident) else {
+        println!("{}", format!("No document '{ident}' found. See `documents list`").bright_red());
+        return;
+    };
+
+    let downloaded = stine.download_document(document)
+        .with_context(|| format!("Failed downloading {}", document.name)).unwrap();
+
+    let file_name = sanitize_file_name(&downloaded.metadata.file_name
+        .unwrap_or_else(|| format!("{}.pdf", document.name)));
+    let path = match file {
+        Some(file) if file.is_dir() => file.join(file_name),
+        Some(file) => file.clone(),
+        None => unique_path(PathBuf::from(file_name)),
+    };
+
+    fs::write(&path, downloaded.content)
+        .with_context(|| format!("Failed writing to {}", path.display())).unwrap();
+    println!("{} {} [{}]", "Downloaded".bright_green(), document.name,
+             path.display().to_string().underline());
+}
+
+pub(crate) fn documents_command(sub_matches: &ArgMatches, stine: &Stine) {
+    match sub_matches.subcommand() {
+        Some(("list", list_matches)) => {
+            let documents = fetch_documents(stine);
+
+            let mut table = Table::new();
+            table.add_row(row!["#", "Name", "Date", "Status"]);
+            for (i, document) in documents.iter().enumerate() {
+                table.add_row(row![
+                    i,
+                    document.name,
+                    DateTime::<Local>::from(document.datetime).format("%Y-%m-%d %H:%M"),
+                    document.status.as_deref().unwrap_or("-"),
+                ]);
+            }
+            output::print_output(OutputFormat::from_matches(list_matches), &table, &documents);
+        }
+        Some(("get", get_matches)) => {
+            let ident: &String = get_matches.get_one("DOCUMENT").unwrap();
+            get(stine, ident, get_matches.get_one("file"));
+        }
+        Some(("sync", sync_matches)) => {
+            let dir: &PathBuf = sync_matches.get_one("dir").unwrap();
+            sync(stine, dir);
+        }
+        _ => unimplemented!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use chrono::Utc;
+
+    use stine_rs::{Document, DocumentMetadata, DownloadedDocument};
+
+    use crate::documents::{archive_document, find_document, unique_path};
+
+    #[test]
+    fn test_find_document() {
+        let document = |name: &str| Document {
+            name: name.to_string(),
+            datetime: Utc::now(),
+            status: None,
+            download: String::new(),
+        };
+        let documents = vec![document("Semesterbescheinigung"), document("Studienbescheinigung")];
+
+        assert_eq!(find_document(&documents, "1"), Some(&documents[1]));
+        assert_eq!(find_document(&documents, "2"), None);
+        assert_eq!(find_document(&documents, "semesterbescheinigung"), Some(&documents[0]));
+        assert_eq!(find_document(&documents, "Studien"), Some(&documents[1]));
+    }
+
+    #[test]
+    fn test_unique_path() {
+        let dir = std::env::temp_dir().join("stine-cli-documents-test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Bescheinigung.pdf");
+        fs::write(&path, "").unwrap();
+        fs::remove_file(dir.join("Bescheinigung (1).pdf")).ok();
+
+        assert_eq!(unique_path(dir.join("new.pdf")), dir.join("new.pdf"));
+        assert_eq!(unique_path(path), dir.join("Bescheinigung (1).pdf"));
+    }
+
+    #[test]
+    fn test_archive_document() {
+        let dir = std::env::temp_dir().join("stine-cli-documents-archive-test");
+        fs::remove_dir_all(&dir).ok();
+        let document = Document {
+            name: "Studienbescheinigung".to_string(),
+            datetime: Utc::now(),
+            status: None,
+            download: String::new(),
+        };
+        let download = |content: &str| DownloadedDocument {
+            metadata: DocumentMetadata {
+                file_name: Some("Studienbescheinigung.pdf".to_string()),
+                content_type: None,
+                sha256: format!("{:x}", content.len()),
+                size: content.len() as u64,
+            },
+            content: content.as_bytes().to_vec(),
+        };
+
+        let entry = archive_document(&dir, &[], &document, |_| Ok(download("first"))).unwrap().unwrap();
+        assert!(dir.join(&entry.path).exists());
+
+        // same name and date, so the regenerated document isn't downloaded again
+        let second = archive_document(&dir, &[entry], &document, |_| panic!("Downloaded an archived document"));
+        assert!(second.unwrap().is_none());
+    }
+}
@@ -0,0 +1,219 @@
+use anyhow::Context;
+use clap::{arg, ArgMatches, Command, value_parser};
+use colored::Colorize;
+use prettytable::{row, Table};
+
+use stine_rs::{CourseResult, GradeStats, LazyLevel, Stine};
+use stine_rs::mobile::{ExamGrade, ExamHistory};
+
+use crate::output;
+use crate::output::OutputFormat;
+
+// max length of a histogram bar
+const BAR_WIDTH: usize = 40;
+
+pub(crate) fn grade_stats_subcommand() -> Command {
+    Command::new("grade-stats")
+        .about("Print the grade distribution of a course as histogram")
+        .arg(arg!(<COURSE> "Course number (64-074) or name of a course in your semester results")
+            .value_parser(value_parser!(String)))
+        .arg(arg!(-a --attempt <ATTEMPT> "Only show the results of this exam attempt. 0 includes all attempts")
+            .required(false)
+            .value_parser(value_parser!(u8).range(0..=99)))
+}
+
+fn find_course(courses: Vec<CourseResult>, ident: &str) -> Option<CourseResult> {
+    let ident_lower = ident.to_lowercase();
+    let (exact, other): (Vec<CourseResult>, Vec<CourseResult>) = courses.into_iter()
+        .partition(|c| c.number.eq_ignore_ascii_case(ident));
+
+    exact.into_iter().next()
+        .or_else(|| other.into_iter().find(|c| c.name.to_lowercase().contains(&ident_lower)))
+}
+
+/// Length of the bar of `count`, relative to the largest count
+pub(crate) fn bar_width(count: i32, max_count: i32) -> usize {
+    if max_count <= 0 {
+        return 0;
+    }
+    (count.max(0) as usize * BAR_WIDTH).div_ceil(max_count as usize)
+}
+
+/// Percentage of results which are as good as or better than `grade`. Lower grades are better
+pub(crate) fn top_percent(grade_map: &[(f32, i32)], grade: f32) -> Option<f32> {
+    let total: i32 = grade_map.iter().map(|(_, count)| count).sum();
+    if total == 0 {
+        return None;
+    }
+
+    let as_good_or_better: i32 = grade_map.iter()
+        .filter(|(g, _)| *g <= grade + f32::EPSILON)
+        .map(|(_, count)| count)
+        .sum();
+    Some(as_good_or_better as f32 / total as f32 * 100.0)
+}
+
+/// Numeric grade of the `attempt` of the exams of the course
+fn attempt_grade(histories: &[ExamHistory], course_number: &str, attempt: u8) -> Option<f32> {
+    let prefix = format!("{course_number} ");
+    histories.iter()
+        .filter(|h| h.context.starts_with(&prefix))
+        .flat_map(|h| &h.attempts)
+        .filter(|a| a.attempt == attempt)
+        .find_map(|a| match a.exam.grade {
+            Some(ExamGrade::Numeric(grade)) => Some(grade),
+            _ => None,
+        })
+}
+
+fn print_histogram(stats: &GradeStats, own_grade: Option<f32>) {
+    let max_count = stats.grade_map.iter().map(|(_, count)| *count).max().unwrap_or_default();
+
+    for (grade, count) in &stats.grade_map {
+        let bar = "█".repeat(bar_width(*count, max_count));
+        let line = format!("{grade:>4.1} │ {bar} {count}");
+
+        if own_grade.is_some_and(|own| (own - grade).abs() < f32::EPSILON) {
+            println!("{} {}", line.bright_green().bold(), "◀ you".bright_green());
+        } else {
+            println!("{line}");
+        }
+    }
+}
+
+fn print_counts(stats: &GradeStats) {
+    let counts = [
+        ("Available results", stats.available_results),
+        ("Results with differing grading system", stats.differing_gs_results),
+        ("Missing (ill)", stats.missing_ill),
+        ("Missing (excused)", stats.missing_excused),
+        ("Missing (without reason)", stats.missing_without_reason),
+        ("Missing (canceled)", stats.missing_canceled),
+    ];
+
+    for (name, count) in counts {
+        if let Some(count) = count {
+            println!("{} {count}", format!("{name}:").bold());
+        }
+    }
+    for (reason, count) in &stats.missing_other {
+        println!("{} {count}", format!("Missing ({reason}):").bold());
+    }
+}
+
+pub(crate) fn grade_stats_command(sub_matches: &ArgMatches, stine: &Stine) {
+    let ident: &String = sub_matches.get_one("COURSE").unwrap();
+    let attempt: Option<&u8> = sub_matches.get_one("attempt");
+
+    let format = OutputFormat::from_matches(sub_matches);
+
+    let mut spinner = output::spinner("Fetching grade stats");
+    let courses: Vec<CourseResult> = stine.get_all_semester_results(LazyLevel::FullLazy)
+        .context("Failed fetching semester results").unwrap()
+        .into_iter().flat_map(|s| s.courses).collect();
+
+    let Some(mut course) = find_course(courses, ident) else {
+        output::stop_spinner(&mut spinner);
+        println!("{}", format!("No course '{ident}' found in your semester results").bright_red());
+        return;
+    };
+
+    let stats = match attempt {
+        Some(attempt) => course.grade_stats_id()
+            .map(|id| stine.get_grade_stats_for_exam(id, *attempt)),
+        None => course.get_grade_stats(stine),
+    };
+    output::stop_spinner(&mut spinner);
+
+    let Some(stats) = stats else {
+        println!("{}", format!("STINE has no grade stats for {} {}", course.number, course.name).bright_red());
+        return;
+    };
+
+    if !format.is_table() {
+        let mut table = Table::new();
+        table.add_row(row!["Grade", "Count"]);
+        for (grade, count) in &stats.grade_map {
+            table.add_row(row![grade, count]);
+        }
+        output::print_output(format, &table, &stats);
+        return;
+    }
+
+    // the stats of a single attempt are compared to the grade of that attempt
+    let own_grade = match attempt {
+        None | Some(0) => course.final_grade,
+        Some(attempt) => stine.get_exam_history().ok()
+            .and_then(|histories| attempt_grade(&histories, &course.number, *attempt)),
+    };
+
+    println!("{} {}", course.number.bold(), course.name.bold());
+    if let Some(average) = stats.average {
+        println!("{} {average:.2}", "Average:".bold());
+    }
+    println!();
+
+    print_histogram(&stats, own_grade);
+    println!();
+
+    if let Some(grade) = own_grade {
+        if let Some(top) = top_percent(&stats.grade_map, grade) {
+            println!("Your grade {grade:.1} is in the top {}", format!("{top:.1}%").bright_green().bold());
+        }
+    }
+    print_counts(&stats);
+}
+
+#[cfg(test)]
+mod tests {
+    use stine_rs::mobile::{ExamHistory, StudentExamRaw};
+
+    use crate::grade_stats::{attempt_grade, bar_width, BAR_WIDTH, top_percent};
+
+    #[test]
+    fn test_bar_width() {
+        assert_eq!(bar_width(10, 10), BAR_WIDTH);
+        assert_eq!(bar_width(5, 10), BAR_WIDTH / 2);
+        assert_eq!(bar_width(1, 1000), 1);
+        assert_eq!(bar_width(0, 10), 0);
+        assert_eq!(bar_width(0, 0), 0);
+    }
+
+    #[test]
+    fn test_top_percent() {
+        let grade_map = vec![(1.0, 5), (2.0, 10), (3.0, 5), (5.0, 0)];
+
+        assert_eq!(top_percent(&grade_map, 1.0), Some(25.0));
+        assert_eq!(top_percent(&grade_map, 2.0), Some(75.0));
+        assert_eq!(top_percent(&grade_map, 5.0), Some(100.0));
+        assert_eq!(top_percent(&[], 1.0), None);
+    }
+
+    #[test]
+    fn test_attempt_grade() {
+        let exam = |due_date: &str, grade: &str| StudentExamRaw {
+            exam_id: due_date.to_string(),
+            exam_name: "Klausur".to_string(),
+            context: "64-074 Berechenbarkeit".to_string(),
+            context_type: "course".to_string(),
+            subject: String::new(),
+            begin_date: String::new(),
+            due_date: due_date.to_string(),
+            time_from: String::new(),
+            time_to: String::new(),
+            grade: grade.to_string(),
+            grade_description: String::new(),
+            instructor_string: String::new(),
+            status: String::new(),
+            status_system: String::new(),
+            semester_id: String::new(),
+            semester_name: String::new(),
+        }.into();
+
+        let histories = ExamHistory::from_exams(vec![exam("20.03.2024", "2,3"), exam("15.02.2024", "5,0")]);
+        assert_eq!(attempt_grade(&histories, "64-074", 1), Some(5.0));
+        assert_eq!(attempt_grade(&histories, "64-074", 2), Some(2.3));
+        assert_eq!(attempt_grade(&histories, "64-074", 3), None);
+        assert_eq!(attempt_grade(&histories, "64-040", 1), None);
+    }
+}
@@ -0,0 +1,388 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use chrono::Local;
+use clap::ValueEnum;
+use lazy_static::lazy_static;
+use log::kv::{Key, Value, VisitSource};
+use log::{LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map};
+use simplelog::{ColorChoice, CombinedLogger, Config, SharedLogger, TerminalMode, TermLogger, WriteLogger};
+
+use crate::config::Settings;
+use crate::redact::RedactingLogger;
+
+lazy_static! {
+    pub(crate) static ref DEFAULT_LOG_PATH: PathBuf = dirs::home_dir().unwrap().join("stine-cli.log");
+}
+
+/// Rotate the log file once it's larger than 10 MiB
+pub(crate) const DEFAULT_MAX_SIZE: u64 = 10 * 1024 * 1024;
+/// Number of rotated log files kept, e.g. `stine-cli.log.1` to `stine-cli.log.3`
+pub(crate) const DEFAULT_KEEP: usize = 3;
+
+/// Settings of the `[log]` section of the config file
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+#[serde(default)]
+pub(crate) struct LogConfig {
+    pub path: Option<PathBuf>,
+    /// Level of the log file. The terminal uses `-v`
+    pub level: Option<LogLevel>,
+    pub format: Option<LogFormat>,
+    /// Only records of targets starting with one of these are logged. Defaults to `stine`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<String>,
+    /// Size after which the log file is rotated, e.g. `10M`. `0` disables it
+    pub max_size: Option<String>,
+    /// Age in days after which the log file is rotated
+    pub max_age_days: Option<u64>,
+    /// Number of rotated log files to keep
+    pub keep: Option<usize>,
+}
+
+impl LogConfig {
+    pub(crate) fn is_default(&self) -> bool {
+        *self == LogConfig::default()
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => LevelFilter::Off,
+            LogLevel::Error => LevelFilter::Error,
+            LogLevel::Warn => LevelFilter::Warn,
+            LogLevel::Info => LevelFilter::Info,
+            LogLevel::Debug => LevelFilter::Debug,
+            LogLevel::Trace => LevelFilter::Trace,
+        }
+    }
+}
+
+/// Format of the log file
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LogFormat {
+    /// Human readable lines
+    #[default]
+    Text,
+    /// One json object per line, including the command, `prgname` and `duration_ms` of requests
+    Json,
+}
+
+/// Parses sizes like `512K`, `10M`, `1GB` or plain bytes
+pub(crate) fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim().to_uppercase();
+    let value = value.strip_suffix("IB").or_else(|| value.strip_suffix('B')).unwrap_or(&value);
+    let (number, factor) = match value.chars().last() {
+        Some('K') => (&value[..value.len() - 1], 1024),
+        Some('M') => (&value[..value.len() - 1], 1024 * 1024),
+        Some('G') => (&value[..value.len() - 1], 1024 * 1024 * 1024),
+        _ => (value, 1),
+    };
+    number.trim().parse::<u64>()
+        .map(|n| n * factor)
+        .map_err(|_| format!("'{value}' is not a size like 10M"))
+}
+
+/// Path of the `n`th rotated log file, e.g. `stine-cli.log.2`
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{n}"));
+    PathBuf::from(name)
+}
+
+/// Rotates the log file, if it's larger than `max_size` or older than `max_age`.
+/// The oldest files are removed, so at most `keep` rotated files exist. Returns whether it was rotated
+pub(crate) fn rotate(path: &Path, max_size: u64, max_age: Option<Duration>, keep: usize) -> io::Result<bool> {
+    let Ok(metadata) = fs::metadata(path) else {
+        return Ok(false);
+    };
+
+    let too_large = max_size > 0 && metadata.len() >= max_size;
+    // the creation time isn't available on every filesystem
+    let created = metadata.created().or_else(|_| metadata.modified())?;
+    let too_old = max_age.is_some_and(|max_age|
+        SystemTime::now().duration_since(created).unwrap_or_default() >= max_age);
+    if !too_large && !too_old {
+        return Ok(false);
+    }
+
+    if keep == 0 {
+        fs::remove_file(path)?;
+        return Ok(true);
+    }
+
+    let oldest = rotated_path(path, keep);
+    if oldest.exists() {
+        fs::remove_file(oldest)?;
+    }
+    for n in (1..keep).rev() {
+        let rotated = rotated_path(path, n);
+        if rotated.exists() {
+            fs::rename(rotated, rotated_path(path, n + 1))?;
+        }
+    }
+    fs::rename(path, rotated_path(path, 1))?;
+    Ok(true)
+}
+
+/// Collects the key values of a record, like `prgname` and `duration_ms` of requests
+struct JsonFields<'a>(&'a mut Map<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), log::kv::Error> {
+        let value = if let Some(number) = value.to_u64() {
+            json!(number)
+        } else if let Some(number) = value.to_i64() {
+            json!(number)
+        } else if let Some(number) = value.to_f64() {
+            json!(number)
+        } else if let Some(boolean) = value.to_bool() {
+            json!(boolean)
+        } else {
+            json!(value.to_string())
+        };
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+/// Formats the record as single json line
+fn json_line(record: &Record, command: Option<&str>) -> String {
+    let mut fields = Map::new();
+    fields.insert("time".to_string(), json!(Local::now().to_rfc3339()));
+    fields.insert("level".to_string(), json!(record.level().as_str()));
+    fields.insert("target".to_string(), json!(record.target()));
+    fields.insert("message".to_string(), json!(record.args().to_string()));
+    if let Some(command) = command {
+        fields.insert("command".to_string(), json!(command));
+    }
+    record.key_values().visit(&mut JsonFields(&mut fields)).ok();
+    serde_json::Value::Object(fields).to_string()
+}
+
+/// Writes records as json lines, see [`LogFormat::Json`]
+struct JsonLogger {
+    level: LevelFilter,
+    targets: Vec<String>,
+    /// Subcommand of this run, e.g. `notify`
+    command: Option<String>,
+    file: Mutex<File>,
+}
+
+impl Log for JsonLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+            && self.targets.iter().any(|target| metadata.target().starts_with(target.as_str()))
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let mut file = self.file.lock().unwrap();
+            writeln!(file, "{}", json_line(record, self.command.as_deref())).ok();
+        }
+    }
+
+    fn flush(&self) {
+        self.file.lock().unwrap().flush().ok();
+    }
+}
+
+impl SharedLogger for JsonLogger {
+    fn level(&self) -> LevelFilter {
+        self.level
+    }
+
+    fn config(&self) -> Option<&Config> {
+        None
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+// disables logging to the terminal, e.g. while the terminal UI is shown
+static TERMINAL_MUTED: AtomicBool = AtomicBool::new(false);
+
+/// Stops or resumes logging to the terminal. The log file is not affected
+pub(crate) fn mute_terminal(muted: bool) {
+    TERMINAL_MUTED.store(muted, Ordering::Relaxed);
+}
+
+/// Terminal logger, which can be muted using [`mute_terminal`]
+struct MutableTermLogger(Box<TermLogger>);
+
+impl Log for MutableTermLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        !TERMINAL_MUTED.load(Ordering::Relaxed) && self.0.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !TERMINAL_MUTED.load(Ordering::Relaxed) {
+            self.0.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.0.flush();
+    }
+}
+
+impl SharedLogger for MutableTermLogger {
+    fn level(&self) -> LevelFilter {
+        self.0.level()
+    }
+
+    fn config(&self) -> Option<&Config> {
+        self.0.config()
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+/// Logs to the terminal using `terminal_level` and to the log file of the settings.
+/// Rotates the log file before opening it
+pub(crate) fn init(settings: &Settings, terminal_level: LevelFilter, command: Option<&str>) -> anyhow::Result<()> {
+    let path = settings.log_path.value.clone().unwrap();
+    let level: LevelFilter = settings.log_level.value.unwrap().into();
+    let targets = settings.log_targets.value.clone().unwrap();
+
+    let mut config_builder = simplelog::ConfigBuilder::new();
+    for target in &targets {
+        config_builder.add_filter_allow(target.clone());
+    }
+    let log_config = config_builder.build();
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).ok();
+    }
+    let max_age = settings.log_max_age_days.value.map(|days| Duration::from_secs(days * 24 * 60 * 60));
+    let rotated = rotate(&path, settings.log_max_size.value.unwrap(), max_age, settings.log_keep.value.unwrap())
+        .with_context(|| format!("Failed rotating log file: {}", path.display()))?;
+
+    let log_file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(&path)
+        .with_context(|| format!("Failed writing to log file: {}", path.display()))?;
+
+    let file_logger: Box<dyn SharedLogger> = match settings.log_format.value.unwrap_or_default() {
+        LogFormat::Text => WriteLogger::new(level, log_config.clone(), log_file),
+        LogFormat::Json => Box::new(JsonLogger {
+            level,
+            targets,
+            command: command.map(str::to_string),
+            file: Mutex::new(log_file),
+        }),
+    };
+
+    // session ids and passwords are masked before records reach the terminal or the log file
+    RedactingLogger::init(
+        CombinedLogger::new(
+            vec![
+                Box::new(MutableTermLogger(
+                    TermLogger::new(terminal_level, log_config, TerminalMode::Mixed, ColorChoice::Auto))),
+                file_logger,
+            ]
+        ),
+        terminal_level.max(level),
+    )?;
+
+    if rotated {
+        log::info!("Rotated log file {}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::Duration;
+
+    use log::{Level, Record};
+
+    use crate::logging::{json_line, parse_size, rotate, rotated_path};
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024"), Ok(1024));
+        assert_eq!(parse_size("512K"), Ok(512 * 1024));
+        assert_eq!(parse_size("10M"), Ok(10 * 1024 * 1024));
+        assert_eq!(parse_size("10 MiB"), Ok(10 * 1024 * 1024));
+        assert_eq!(parse_size("1gb"), Ok(1024 * 1024 * 1024));
+        assert!(parse_size("ten").is_err());
+        assert!(parse_size("").is_err());
+    }
+
+    #[test]
+    fn test_rotate() {
+        let dir = std::env::temp_dir().join("stine-cli-logging-test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("stine-cli.log");
+
+        assert!(!rotate(&path, 10, None, 2).unwrap());
+
+        fs::write(&path, "small").unwrap();
+        assert!(!rotate(&path, 10, None, 2).unwrap());
+
+        for content in ["first rotation", "second rotation", "third rotation"] {
+            fs::write(&path, content).unwrap();
+            assert!(rotate(&path, 10, None, 2).unwrap());
+        }
+        assert!(!path.exists());
+        assert_eq!(fs::read_to_string(rotated_path(&path, 1)).unwrap(), "third rotation");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 2)).unwrap(), "second rotation");
+        assert!(!rotated_path(&path, 3).exists());
+
+        fs::write(&path, "small").unwrap();
+        assert!(rotate(&path, 0, Some(Duration::ZERO), 2).unwrap());
+        assert_eq!(fs::read_to_string(rotated_path(&path, 1)).unwrap(), "small");
+
+        fs::write(&path, "removed").unwrap();
+        assert!(rotate(&path, 1, None, 0).unwrap());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_json_line() {
+        let kvs = [("prgname", log::kv::Value::from("COURSERESULTS")), ("duration_ms", log::kv::Value::from(231u64))];
+        let line = json_line(&Record::builder()
+            .level(Level::Debug)
+            .target("stine_rs::stine")
+            .args(format_args!("POST COURSERESULTS took 231ms"))
+            .key_values(&kvs)
+            .build(), Some("semester-results"));
+
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["level"], "DEBUG");
+        assert_eq!(json["target"], "stine_rs::stine");
+        assert_eq!(json["message"], "POST COURSERESULTS took 231ms");
+        assert_eq!(json["command"], "semester-results");
+        assert_eq!(json["prgname"], "COURSERESULTS");
+        assert_eq!(json["duration_ms"], 231);
+        assert!(!line.contains('\n'));
+    }
+}
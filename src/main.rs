@@ -1,10 +1,11 @@
 extern crate core;
 
 
-use std::{env, fs};
-use std::fs::{File, OpenOptions};
-use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::collections::BTreeMap;
+use std::env;
+use std::io;
+use std::io::{BufRead, IsTerminal};
+use std::path::PathBuf;
 use std::process::exit;
 use std::str::FromStr;
 
@@ -14,55 +15,36 @@ use clap::{Arg, arg, ArgAction, ArgMatches, Args, command, Command, FromArgMatch
 use clap_verbosity_flag::Verbosity;
 use colored::Colorize;
 use either::Either;
-use if_chain::if_chain;
-use lazy_static::lazy_static;
-use log::info;
+use log::{info, warn};
 use prettytable::{Cell, row, Table};
 use serde::{Deserialize, Serialize};
-use simplelog::{ColorChoice, CombinedLogger, LevelFilter, TerminalMode, TermLogger, WriteLogger};
-use spinners::{Spinner, Spinners};
+use simplelog::LevelFilter;
 use thiserror::Error;
 
 use stine_rs::{EventType, LazyLevel, SemesterResult, SemesterType, Stine};
 use stine_rs::Semester as SemesterStine;
-
+use stine_rs::mobile::ExamHistory;
+
+use crate::config::{CONFIG_PATH, ConfigFile, Credentials, LEGACY_CONFIG_PATH, Session, SESSIONS_PATH, Settings, Source};
+use crate::store::{SecretStore, STORE_PATH};
+
+mod completions;
+mod config;
+mod courses;
+mod debug;
+mod documents;
+mod grade_stats;
+mod logging;
+mod materials;
+mod messages;
 mod notify;
-
-// reusing the config as env file ( ͠° ͟ʖ ͡°), don't know if good or bad ( ͡ʘ ͜ʖ ͡ʘ)
-lazy_static! {
-    static ref CONFIG_PATH: PathBuf = env::current_exe().unwrap().parent().unwrap().join(".stine-env");
-}
-
-#[derive(Debug, Serialize, Deserialize, Default)]
-struct Config {
-    username: String,
-    password: String,
-    session: String,
-    cnsc_cookie: String,
-    /// Last usage of session cookies as unix timestamp
-    last_used: Option<i64>,
-}
-
-
-fn load_cfg(config_path: &Path) -> anyhow::Result<Config> {
-    if config_path.exists() {
-        let config: Config = toml::from_str(&fs::read_to_string(config_path)?)?;
-
-        return Ok(config);
-    }
-    Ok(Config::default())
-}
-
-fn save_cfg(config_path: &Path, cfg: &mut Config) -> anyhow::Result<()> {
-    if !config_path.exists() {
-        fs::create_dir_all(config_path.parent().unwrap_or_else(|| Path::new("")))?;
-    }
-
-    cfg.last_used = Some(Utc::now().timestamp());
-    let mut buffer = File::create(config_path)?;
-    buffer.write_all(toml::to_string_pretty(&cfg)?.as_bytes())?;
-    Ok(())
-}
+mod output;
+mod password;
+mod periods;
+mod redact;
+mod shell;
+mod store;
+mod tui;
 
 fn unwrap_option_or_generic<T, F>(option: Option<T>, fallback: F) -> Either<T, F> {
     if let Some(value) = option {
@@ -72,60 +54,71 @@ fn unwrap_option_or_generic<T, F>(option: Option<T>, fallback: F) -> Either<T, F
     }
 }
 
-/// Loads credentials for authentication
-/// If credentials are passed as cli arg, these are prioritized and updated in the returned Config
-fn get_credentials(matches: &ArgMatches) -> Config {
-    let mut auth_cfg = Config::default();
-
-    let username = matches.get_one::<String>("username");
-    let password = matches.get_one::<String>("password");
-
-    if_chain! {
-        if let Some(username) = username;
-        if let Some(password) = password;
-        then {
-
-            // if &auth_cfg.username != username || &auth_cfg.password != password {
-            //     println!("Passed credentials {}:{}, differ from config credentials {}:{}",
-            //                      username, password,
-            //                      auth_cfg.username, auth_cfg.password);
-            // }
-
-            if matches.get_flag("save_config") {
-                save_cfg(&CONFIG_PATH, &mut auth_cfg)
-                .with_context(|| format!("Failed saving config to {}", &CONFIG_PATH.display())).unwrap();
-                println!("{} [{}]",
-                 "> Saved credentials to config file".bright_green(),
-                 &CONFIG_PATH.display().to_string().underline());
+/// Loads credentials for authentication from the resolved settings
+/// Reads the password from stdin, the password command or a prompt if necessary.
+/// Credentials passed as cli args are saved to the profile when using --save_config
+fn get_credentials(matches: &ArgMatches, settings: &mut Settings, config: &mut ConfigFile,
+                   secret_store: Option<&mut SecretStore>) -> Credentials {
+    let profile_name = settings.profile_name().to_string();
+
+    let interactive = io::stdin().is_terminal() && settings.username.value.is_some();
+    // --password-stdin is read before authenticating, see `read_stdin_password`
+    settings.read_password(None, interactive)
+        .context("Failed reading password").unwrap();
+
+    let Some(credentials) = settings.credentials() else {
+        eprintln!("{}", format!("Can't load credentials of profile '{profile_name}' from config file at: {} \
+        (Look at the README.md for slightly more info). \
+        Please use --username <USERNAME> and --password <PASSWORD> or STINE_USERNAME and STINE_PASSWORD for authentication.",
+                                &CONFIG_PATH.display()).red());
+        exit(-1);
+    };
+
+    let username_flag = matches!(settings.username.source, Source::Flag(_));
+    let password_flag = matches!(settings.password.source, Source::Flag(_));
+    if (username_flag || password_flag) && matches.get_flag("save_config") {
+        // passwords of other sources, like prompts, are not meant to be stored in plaintext
+        let profile = config.profile.entry(profile_name.clone()).or_default();
+        if username_flag {
+            profile.username = Some(credentials.username.clone());
+        }
+        match secret_store {
+            Some(secret_store) if password_flag => {
+                secret_store.profile_mut(&profile_name).password = Some(credentials.password.clone());
+                secret_store.save().context("Failed saving password to the encrypted store").unwrap();
             }
+            _ if password_flag => profile.password = Some(credentials.password.clone()),
+            _ => {}
+        }
 
-            auth_cfg.username = username.to_string();
-            auth_cfg.password = password.to_string();
-
-        } else {
-            // if no auth arg passed, try to load them from config file
-
-            let use_auth_arg_msg = format!("Can't load credentials from config-file at: {} \
-            (Look at the README.md for slightly more info). \
-            Please use --username <USERNAME> and --password <PASSWORD> for authentication.",
-                &CONFIG_PATH.display());
-
-            let cfg: Config = load_cfg(&CONFIG_PATH)
-                .with_context(|| format!("Failed loading config file from: {}", &CONFIG_PATH.display())).unwrap();
+        config::save_toml(&CONFIG_PATH, config)
+            .with_context(|| format!("Failed saving config to {}", &CONFIG_PATH.display())).unwrap();
+        eprintln!("{} [{}]",
+                  format!("> Saved credentials to profile '{profile_name}'").bright_green(),
+                  &CONFIG_PATH.display().to_string().underline());
+    } else {
+        eprintln!("Using username from {} and password from {} (profile '{profile_name}')",
+                  settings.username.source, settings.password.source);
+    }
 
-            // config is empty
-            if cfg.username.is_empty() || cfg.password.is_empty() {
-                eprintln!("{}", use_auth_arg_msg.red());
-                exit(-1);
-            } else {
-                println!("Loading username and password from config file: [{}]", &CONFIG_PATH.display());
-                auth_cfg = cfg;
-            }
+    credentials
+}
 
-        }
+/// Reads the password of --password-stdin from the first line of `stdin`. Also done if the session is still valid,
+/// so the email password of --email-password-stdin is always read from the following line
+fn read_stdin_password(matches: &ArgMatches, settings: &mut Settings, stdin: &mut dyn BufRead) -> anyhow::Result<()> {
+    if matches.get_flag("password-stdin") {
+        settings.read_password(Some(stdin), false)?;
     }
+    Ok(())
+}
 
-    auth_cfg
+/// Prints the error of a command and exits
+fn exit_on_error(result: anyhow::Result<()>) {
+    if let Err(err) = result {
+        eprintln!("{}", format!("{err:#}").bright_red());
+        exit(-1);
+    }
 }
 
 fn check_network_connection() -> bool {
@@ -133,10 +126,12 @@ fn check_network_connection() -> bool {
 }
 
 
-fn authenticate(auth_cfg: &Config, check_network: bool) -> Stine {
+/// Authenticates using the session cookies, or the credentials if the session expired.
+/// `get_credentials` is only called if needed, as it may prompt for the password
+fn authenticate(session: &Session, check_network: bool, get_credentials: impl FnOnce() -> Credentials) -> Stine {
     let last_used_dt = DateTime::from_utc(
         NaiveDateTime::from_timestamp_opt(
-            auth_cfg.last_used.unwrap_or(Utc::now().timestamp()), 0,
+            session.last_used.unwrap_or(Utc::now().timestamp()), 0,
         )
             .unwrap_or(Utc::now().naive_utc()), Utc,
     );
@@ -144,18 +139,22 @@ fn authenticate(auth_cfg: &Config, check_network: bool) -> Stine {
     let max_timeout = 30;
     let no_timeout = (Utc::now() - last_used_dt).num_minutes() < max_timeout;
 
-    if !auth_cfg.session.is_empty() && !auth_cfg.cnsc_cookie.is_empty()
+    redact::add_secret(&session.session);
+    redact::add_secret(&session.cnsc_cookie);
+
+    if !session.session.is_empty() && !session.cnsc_cookie.is_empty()
         && no_timeout {
-        println!("> Authenticating using session cookies");
-        if let Ok(stine_session) = Stine::new_session(&auth_cfg.cnsc_cookie, &auth_cfg.session) {
+        eprintln!("> Authenticating using session cookies");
+        if let Ok(stine_session) = Stine::new_session(&session.cnsc_cookie, &session.session) {
             return stine_session;
         } else {
-            println!("{}", "Failed authenticating using session cookies.".red());
-            println!("> Using credentials");
+            eprintln!("{}", "Failed authenticating using session cookies.".red());
+            eprintln!("> Using credentials");
         }
     }
 
-    match Stine::new(auth_cfg.username.as_str(), auth_cfg.password.as_str()) {
+    let credentials = get_credentials();
+    match Stine::new(credentials.username.as_str(), credentials.password.as_str()) {
         Ok(stine) => stine,
         Err(error) => {
             if check_network && !check_network_connection() {
@@ -172,7 +171,10 @@ fn authenticate(auth_cfg: &Config, check_network: bool) -> Stine {
 
 #[derive(ValueEnum, Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
 enum Language {
+    // lowercase aliases match the cli values, e.g. in the config file
+    #[serde(alias = "german")]
     German,
+    #[serde(alias = "english")]
     English,
 }
 
@@ -195,6 +197,25 @@ impl From<stine_rs::Language> for Language {
 }
 
 
+/// Source of the data. See [`stine_rs::Backend`]
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+enum Backend {
+    /// Scrape the STINE website
+    Html,
+    /// Use the api of the STINE mobile app. Falls back to html if a request fails
+    Mobile,
+}
+
+impl Backend {
+    fn get(self) -> Box<dyn stine_rs::Backend> {
+        match self {
+            Backend::Html => Box::new(stine_rs::HtmlBackend),
+            Backend::Mobile => Box::new(stine_rs::mobile::MobileBackend),
+        }
+    }
+}
+
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Semester {
     pub season: SemesterType,
@@ -271,24 +292,59 @@ struct DerivedArgs {
 fn get_command() -> Command {
     let command = command!().disable_colored_help(false)
         .arg(
-            arg!(--username <USERNAME> "Username for the stine login. Alternatively use .env file")
+            arg!(--username <USERNAME> "Username for the stine login. Alternatively use STINE_USERNAME or the config file")
                 .required(false)
                 .value_parser(value_parser!(String))
         )
         .arg(
-            arg!(--password <PASSWORD> "Password for the stine login. Alternatively use .env file")
+            arg!(--password <PASSWORD> "Password for the stine login. Visible to other users, prefer --password-stdin, \
+            the password_command of the config or the prompt")
                 .required(false)
                 .value_parser(value_parser!(String))
         )
         .arg(
-            arg!(--save_config "Save username and password to .env file")
+            arg!(--"password-stdin" "Read the password from the first line of stdin")
                 .required(false)
+                .conflicts_with("password")
                 .action(ArgAction::SetTrue)
         )
+        .arg(
+            arg!(--save_config "Save username and password to the selected profile and the session cookies")
+                .required(false)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(arg!(-p --profile <PROFILE>)
+            .required(false)
+            .global(true)
+            .value_parser(value_parser!(String))
+            .help("Profile of the config file to use. \
+            Defaults to STINE_PROFILE, `default_profile` of the config or 'default'")
+        )
         .arg(arg!(-l --language <LANGUAGE>)
             .required(false)
             .value_parser(value_parser!(Language))
-            .help("Set Stine language. Changes output and language for your account")
+            .help("Set Stine language. Changes output and language for your account. \
+            Defaults to the language of the profile")
+        )
+        .arg(arg!(--"cache-dir" <DIR>)
+            .required(false)
+            .value_parser(value_parser!(PathBuf))
+            .help("Directory of the course cache. Defaults to STINE_CACHE_DIR, `cache_dir` of the profile \
+            or the cache dir of your OS")
+        )
+        .arg(arg!(--backend <BACKEND>)
+            .required(false)
+            .default_value("html")
+            .value_parser(value_parser!(Backend))
+            .help("Where registrations and results are fetched from. \
+            The mobile api is more stable, but provides less info")
+        )
+        .arg(Arg::new(output::FORMAT_ARG).long("output").value_name("FORMAT")
+            .required(false)
+            .global(true)
+            .value_parser(value_parser!(output::OutputFormat))
+            .help("Output format [default: table or the format of the profile]. \
+            Status messages and spinners are written to stderr, so json, csv and markdown can be piped into other programs")
         )
         .arg_required_else_help(true)
         .subcommand_required(true)
@@ -308,10 +364,8 @@ fn get_command() -> Command {
                             .action(ArgAction::SetTrue)
                             .help("Show grade avg of the course. Potentially doubles requests to STINE.")
                     ),
-                // TODO: impl courses subcommand
-                // Command::new("courses")
-                //     .about("Print all available courses")
-                //     .arg(Arg::new("force-refresh").short('f').long("force-refresh")),
+                courses::courses_subcommand(),
+                courses::course_subcommand(),
                 Command::new("registration-status")
                     .about("Print registration status of all applied (sub)-modules")
                     .arg(arg!(-r --reduce).required(false).action(ArgAction::SetTrue)
@@ -323,15 +377,22 @@ fn get_command() -> Command {
                         .required(false)
                         .num_args(0..)
                         .value_parser(value_parser!(notify::NotifyEvent))
-                        .help("Specify events to be notified about. If not specified, you will be notified about all events"))
+                        .help("Specify events to be notified about. \
+                        If not specified, the events of the profile or all events are used"))
                     .arg(arg!(--email_address <EMAIL_ADDRESS>)
-                        .required(true)
+                        .required(false)
                         .value_parser(value_parser!(String))
-                        .help("Email-address used for the notifications"))
+                        .help("Email-address used for the notifications. Alternatively use STINE_EMAIL_ADDRESS or the profile"))
                     .arg(arg!(--email_password <EMAIL_PASSWORD>)
-                        .required(true)
+                        .required(false)
                         .value_parser(value_parser!(String))
-                        .help("Password for the email address"))
+                        .help("Password for the email address. Alternatively use STINE_EMAIL_PASSWORD or the profile. \
+                        Prompted for if not set"))
+                    .arg(arg!(--"email-password-stdin" "Read the email password from a line of stdin, \
+                        after the STINE password if --password-stdin is used")
+                        .required(false)
+                        .conflicts_with("email_password")
+                        .action(ArgAction::SetTrue))
                     .arg(arg!(--smtp_server <SMTP_SERVER>)
                         .required(false)
                         .value_parser(value_parser!(String))
@@ -351,14 +412,30 @@ fn get_command() -> Command {
                         .action(ArgAction::SetTrue)
                         .help("Overwrites the saved language, \
                         will delete old data and replace it with new data in the specified <LANGUAGE> using --language"))
+                    .arg(arg!(--"notify-dir" <DIR>)
+                        .required(false)
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Directory of the data compared between runs. \
+                        Alternatively use STINE_NOTIFY_DIR or `dir` of the notify section of the profile"))
                     .arg(Arg::new("dry").long("dry-run")
                         .required(false)
                         .action(ArgAction::SetTrue).help("Only output to stdout."))
                     .arg(Arg::new("send-test-email").long("send-test-email")
                         .required(false)
                         .action(ArgAction::SetTrue).help("Send a test Email to see if your email credentials work.")),
+                messages::messages_subcommand(),
+                materials::materials_subcommand(),
+                documents::documents_subcommand(),
+                periods::periods_subcommand(),
+                grade_stats::grade_stats_subcommand(),
                 Command::new("check")
-                    .about("Check your credentials and connection to Stine")
+                    .about("Check your credentials and connection to Stine"),
+                shell::shell_subcommand(),
+                tui::tui_subcommand(),
+                completions::completions_subcommand(),
+                completions::man_subcommand(),
+                config::config_subcommand(),
+                debug::debug_subcommand(),
             ],
         );
 
@@ -378,46 +455,27 @@ fn colorize_event_type(str: String, event_type: Option<EventType>) -> colored::C
     }
 }
 
-fn main() {
-    let matches = get_command().get_matches();
-
-    let derived_matches = DerivedArgs::from_arg_matches(&matches)
-        .map_err(|err| err.exit())
-        .unwrap();
-    let log_level: LevelFilter = derived_matches.verbose.log_level_filter();
-
-    let log_config = simplelog::ConfigBuilder::new()
-        .add_filter_allow_str("stine")
-        .build();
-
-    let log_path = dirs::home_dir().unwrap().join("stine-cli.log");
-
-    let log_file = OpenOptions::new()
-        .read(true)
-        .append(true)
-        .create(true)
-        .open(&log_path)
-        .with_context(|| format!("Failed writing to log file: {}", log_path.display())).unwrap();
-
-
-    CombinedLogger::init(
-        vec![
-            TermLogger::new(log_level, log_config.clone(), TerminalMode::Mixed, ColorChoice::Auto),
-            WriteLogger::new(LevelFilter::Trace, log_config, log_file),
-        ]
-    ).unwrap();
-
-    info!("LogLevel: [{log_level}]");
-    info!("Using log file at: [{}]", log_path.display());
-
-    let mut auth_cfg = get_credentials(&matches);
-
-    let mut stine: Stine = authenticate(
-        &auth_cfg,
-        matches!(matches.subcommand_name(), Some("check")));
-
-    println!("{}", "Successfully authenticated with Stine".bold().green());
+/// Formats the latest attempt of the exams of the course in `semester`, e.g. "2 (retake)"
+fn format_attempt(histories: &[ExamHistory], course_number: &str, semester: &SemesterStine) -> String {
+    let prefix = format!("{course_number} ");
+    histories.iter()
+        .filter(|h| h.context.starts_with(&prefix))
+        .filter_map(|h| h.attempts.iter()
+            .filter(|a| a.exam.semester.as_ref() == Some(semester))
+            .max_by_key(|a| a.attempt)
+            .map(|attempt| (h, attempt)))
+        .max_by_key(|(_, attempt)| attempt.attempt)
+        .map_or_else(|| "-".to_string(), |(history, latest)| {
+            // only failed attempts before this one make it a retake
+            let retake = history.attempts.iter().any(|a| a.attempt < latest.attempt && a.is_failed());
+            if retake { format!("{} (retake)", latest.attempt) } else { latest.attempt.to_string() }
+        })
+}
 
+/// Runs the subcommand of `matches`, which requires authentication
+fn run_command(matches: &ArgMatches, stine: &mut Stine, settings: &mut Settings) {
+    let output_format = output::OutputFormat::from_matches(matches);
+    let backend = matches.get_one::<Backend>("backend").unwrap().get();
 
     match matches.subcommand() {
         Some(("semester-results", sub_matches)) => {
@@ -426,29 +484,46 @@ fn main() {
                 .unwrap_or_default().cloned().collect();
             let semesters: Vec<SemesterStine> = semesters.iter().cloned().map(SemesterStine::from).collect();
 
-            let mut spinner = Spinner::new(Spinners::Dots,
-                                           "Fetching semester results".into());
+            let mut spinner = output::spinner("Fetching semester results");
 
             // fetch semester results using NotLazy to directly use `GradeStats`
             let lazy_level = if grade_avg { LazyLevel::NotLazy } else { LazyLevel::FullLazy };
-            let semester_results: Vec<SemesterResult> = if semesters.is_empty() {
-                stine.get_all_semester_results(lazy_level)
+            let mut semester_results: Vec<SemesterResult> = if semesters.is_empty() {
+                backend.get_all_semester_results(stine, lazy_level)
                     .unwrap_or_else(|_| { panic!("{}", "Request Error while trying to fetch all semester results".bright_red()) })
             } else {
-                println!("Selected Semesters: {semesters:?}");
-                stine.get_semester_results(semesters, lazy_level)
+                eprintln!("Selected Semesters: {semesters:?}");
+                backend.get_semester_results(stine, semesters, lazy_level)
                     .unwrap_or_else(|_| { panic!("{}", "Request Error while trying to fetch semester results".bright_red()) })
             };
-            spinner.stop();
+            // attempts are only available using the mobile api
+            let with_attempts = *matches.get_one::<Backend>("backend").unwrap() == Backend::Mobile;
+            let histories: Vec<ExamHistory> = if with_attempts {
+                stine.get_exam_history().unwrap_or_else(|err| {
+                    warn!("Failed fetching exam attempts: {err}");
+                    vec![]
+                })
+            } else {
+                vec![]
+            };
+            output::stop_spinner(&mut spinner);
 
             let mut table = Table::new();
+            // one row per course, with the semester as column instead of summary rows
+            let mut flat_table = Table::new();
             let mut header_row = row!["ID", "Name", "Final grade", "Credits", "Status"];
+            if with_attempts {
+                header_row.add_cell(Cell::new("Attempt"));
+            }
             if grade_avg {
                 header_row.add_cell(Cell::new("Grade Avg"))
             }
-            table.add_row(header_row);
-            for semester_result in semester_results {
-                for mut course_result in semester_result.courses {
+            table.add_row(header_row.clone());
+            header_row.insert_cell(0, Cell::new("Semester"));
+            flat_table.add_row(header_row);
+
+            for semester_result in &mut semester_results {
+                for course_result in &mut semester_result.courses {
                     let mut row = row![
                             course_result.number,
                             course_result.name,
@@ -456,12 +531,18 @@ fn main() {
                             course_result.credits.as_ref().unwrap_or(&"-".to_string()),
                             course_result.status,
                     ];
+                    if with_attempts {
+                        row.add_cell(Cell::new(&format_attempt(&histories, &course_result.number,
+                                                               &semester_result.semester)));
+                    }
                     if grade_avg {
-                        let avg_formatted = course_result.get_grade_stats(&stine).map_or_else(
+                        let avg_formatted = course_result.get_grade_stats(stine).map_or_else(
                             || "_".to_string(), |g| g.average.unwrap_or_default().to_string());
                         row.add_cell(Cell::new(&avg_formatted));
                     }
-                    table.add_row(row);
+                    table.add_row(row.clone());
+                    row.insert_cell(0, Cell::new(&semester_result.semester.to_string()));
+                    flat_table.add_row(row);
                 }
 
 
@@ -469,7 +550,7 @@ fn main() {
                     row![
                         format!("Semester [{}]", semester_result.semester.to_string().red()),
                         "",
-                        semester_result.semester_gpa.unwrap_or_default().to_string().green().bold(),
+                        semester_result.semester_gpa.clone().unwrap_or_default().to_string().green().bold(),
                         semester_result.semester_credits.to_string().green().bold(),
                         ""
                     ]
@@ -478,27 +559,34 @@ fn main() {
                 table.add_empty_row();
             }
 
-            println!();
-            table.printstd();
+            if output_format.is_table() {
+                println!();
+                table.printstd();
+            } else {
+                output::print_output(output_format, &flat_table, &semester_results);
+            }
         }
         Some(("registration-status", submatches)) => {
-            let mut spinner = Spinner::new(Spinners::Dots,
-                                           "Fetching registration status".into());
+            let mut spinner = output::spinner("Fetching registration status");
 
-            let registrations = stine.get_my_registrations(LazyLevel::FullLazy).
+            let mut registrations = backend.get_my_registrations(stine, LazyLevel::FullLazy).
                 context("Failed fetching stine registrations").unwrap();
-            spinner.stop();
+            output::stop_spinner(&mut spinner);
+
+            // one row per submodule or module, with the status as column
+            let mut flat_table = Table::new();
+            flat_table.add_row(row!["Status", "Name"]);
 
             let mut table_pending = Table::new();
             table_pending.add_row(row![c => "--- pending ---".bold()]);
-            for mut pending_submodule in registrations.pending_submodules {
+            for pending_submodule in &mut registrations.pending_submodules {
 
                 // colorizing requires an extra request for every submodule
                 // so only do this if reducing of request is not wanted
-                let name = if !submatches.get_flag("reduce") {
+                let name = if !submatches.get_flag("reduce") && output_format.is_table() {
                     colorize_event_type(
                         pending_submodule.name.to_string(),
-                        pending_submodule.info(&stine).event_type)
+                        pending_submodule.info(stine).event_type)
                 } else {
                     pending_submodule.name.to_string().white()
                 };
@@ -506,37 +594,70 @@ fn main() {
                 table_pending.add_row(row![
                         name
                     ]);
+                flat_table.add_row(row!["pending", pending_submodule.name]);
             }
 
             let mut table_accepted = Table::new();
             table_accepted.add_row(row![c => "--- accepted ---".green().bold()]);
-            for accepted_submodule in registrations.accepted_submodules {
+            for accepted_submodule in &registrations.accepted_submodules {
                 table_accepted.add_row(row![accepted_submodule.name]);
+                flat_table.add_row(row!["accepted", accepted_submodule.name]);
             }
 
             let mut table_rejected = Table::new();
             table_rejected.add_row(row![c => "--- Rejected ---".red().bold()]);
-            for rejected_submodule in registrations.rejected_submodules {
+            for rejected_submodule in &registrations.rejected_submodules {
                 table_rejected.add_row(row![rejected_submodule.name]);
+                flat_table.add_row(row!["rejected", rejected_submodule.name]);
             }
 
             let mut table_accepted_modules = Table::new();
             table_accepted_modules.add_row(row![c => "--- accepted modules ---".green().bold()]);
-            for module in registrations.accepted_modules {
+            for module in &registrations.accepted_modules {
                 table_accepted_modules.add_row(row![module.name]);
+                flat_table.add_row(row!["accepted module", module.name]);
             }
 
-            println!();
-            table_pending.printstd();
-            println!();
-            table_accepted.printstd();
-            println!();
-            table_rejected.printstd();
-            println!();
-            table_accepted_modules.printstd();
+            if output_format.is_table() {
+                println!();
+                table_pending.printstd();
+                println!();
+                table_accepted.printstd();
+                println!();
+                table_rejected.printstd();
+                println!();
+                table_accepted_modules.printstd();
+            } else {
+                output::print_output(output_format, &flat_table, &registrations);
+            }
+        }
+        Some(("courses", sub_matches)) => {
+            courses::courses_command(sub_matches, stine);
+        }
+        Some(("course", sub_matches)) => {
+            courses::course_command(sub_matches, stine);
         }
         Some(("notify", sub_matches)) => {
-            notify::notify_command(sub_matches, &mut stine);
+            let mut stdin = io::stdin().lock();
+            let stdin = sub_matches.get_flag("email-password-stdin").then_some(&mut stdin as &mut dyn BufRead);
+            settings.read_email_password(stdin, io::stdin().is_terminal())
+                .context("Failed reading email password").unwrap();
+            notify::notify_command(sub_matches, stine, settings);
+        }
+        Some(("messages", sub_matches)) => {
+            messages::messages_command(sub_matches, stine);
+        }
+        Some(("materials", sub_matches)) => {
+            materials::materials_command(sub_matches, stine);
+        }
+        Some(("documents", sub_matches)) => {
+            documents::documents_command(sub_matches, stine);
+        }
+        Some(("periods", sub_matches)) => {
+            periods::periods_command(sub_matches, stine);
+        }
+        Some(("grade-stats", sub_matches)) => {
+            grade_stats::grade_stats_command(sub_matches, stine);
         }
         Some(("check", _)) => {
             println!("{} {}",
@@ -546,18 +667,149 @@ fn main() {
 
         _ => unimplemented!(),
     }
+}
+
+fn main() {
+    let matches = get_command().get_matches();
+
+    let derived_matches = DerivedArgs::from_arg_matches(&matches)
+        .map_err(|err| err.exit())
+        .unwrap();
+    let log_level: LevelFilter = derived_matches.verbose.log_level_filter();
+
+    // the log file is configured in the config file, so the migration result is only logged afterwards
+    let migration = config::migrate_legacy_config(&LEGACY_CONFIG_PATH, &CONFIG_PATH, &SESSIONS_PATH);
+
+    let mut config: ConfigFile = config::load_toml(&CONFIG_PATH)
+        .with_context(|| format!("Failed loading config file from: {}", &CONFIG_PATH.display())).unwrap();
+    let mut settings = Settings::resolve(&matches, &config, |name| env::var(name).ok())
+        .context("Failed resolving settings").unwrap();
+
+    logging::init(&settings, log_level, matches.subcommand_name())
+        .context("Failed initializing logging").unwrap();
+
+    match migration {
+        Ok(Some(backup_path)) => eprintln!("{} [{}]. The old file was moved to {}",
+                                           "> Migrated .stine-env to the config file".bright_green(),
+                                           &CONFIG_PATH.display().to_string().underline(),
+                                           backup_path.display()),
+        Ok(None) => {}
+        Err(err) => warn!("Failed migrating {}: {err}", LEGACY_CONFIG_PATH.display()),
+    }
+    settings.redact_secrets();
+    let profile_name = settings.profile_name().to_string();
+    if settings.profile.source != Source::Default && !config.profile.contains_key(&profile_name) {
+        warn!("Profile '{profile_name}' does not exist in {}", CONFIG_PATH.display());
+    }
+    info!("Using profile: [{profile_name}]");
+
+    if let Some(format) = settings.output.value {
+        output::OutputFormat::set_default(format);
+    }
+
+    // tables are colorized, but machine-readable output must not contain ansi escape codes
+    let output_format = output::OutputFormat::from_matches(&matches);
+    if !output_format.is_table() {
+        colored::control::set_override(false);
+    }
+
+    info!("LogLevel: [{log_level}]");
+    info!("Using log file at: [{}]", settings.log_path.value.as_ref().unwrap().display());
+
+    // commands which don't need authentication
+    match matches.subcommand() {
+        Some(("debug", sub_matches)) => {
+            exit_on_error(debug::debug_command(sub_matches));
+            return;
+        }
+        Some(("config", sub_matches)) => {
+            config::config_command(sub_matches, &settings, &mut config);
+            return;
+        }
+        Some(("completions", sub_matches)) => {
+            let cache_dir = settings.cache_dir.value.as_ref().unwrap();
+            exit_on_error(completions::completions_command(sub_matches, get_command(), cache_dir));
+            return;
+        }
+        Some(("man", sub_matches)) => {
+            exit_on_error(completions::man_command(sub_matches, get_command()));
+            return;
+        }
+        _ => {}
+    }
 
+    // passwords and sessions of the encrypted store, unlocked once per run
+    let mut secret_store: Option<SecretStore> = settings.uses_store().then(|| {
+        let passphrase = store::read_passphrase(&settings, !STORE_PATH.exists())
+            .context("Failed reading passphrase of the encrypted store").unwrap();
+        let secret_store = SecretStore::open(&STORE_PATH, passphrase)
+            .with_context(|| format!("Failed opening encrypted store {}", STORE_PATH.display())).unwrap();
+        settings.apply_store(&secret_store.profile(&profile_name));
+        secret_store
+    });
+
+    let session = match &secret_store {
+        Some(secret_store) => secret_store.profile(&profile_name).session,
+        None => config::load_toml::<BTreeMap<String, Session>>(&SESSIONS_PATH)
+            .with_context(|| format!("Failed loading sessions from: {}", &SESSIONS_PATH.display())).unwrap()
+            .remove(&profile_name),
+    };
+
+    read_stdin_password(&matches, &mut settings, &mut io::stdin().lock())
+        .context("Failed reading password").unwrap();
 
-    auth_cfg.session = stine.session.unwrap();
-    auth_cfg.cnsc_cookie = stine.cnsc_cookie.unwrap();
+    let mut stine: Stine = authenticate(
+        &session.unwrap_or_default(),
+        matches!(matches.subcommand_name(), Some("check")),
+        || get_credentials(&matches, &mut settings, &mut config, secret_store.as_mut()));
+
+    eprintln!("{}", "Successfully authenticated with Stine".bold().green());
+    redact::add_secret(stine.session.as_deref().unwrap_or_default());
+    redact::add_secret(stine.cnsc_cookie.as_deref().unwrap_or_default());
+
+    // only changes the language of the account, if it differs
+    if let Some(language) = &settings.language.value {
+        stine.set_language(&stine_rs::Language::from(language.clone()))
+            .context("Failed changing language").unwrap();
+    }
+    if settings.cache_dir.source != Source::Default {
+        let cache_dir = settings.cache_dir.value.clone().unwrap();
+        stine = stine.with_cache_dir(cache_dir.clone())
+            .with_context(|| format!("Failed creating cache dir {}", cache_dir.display())).unwrap();
+    }
 
+    match matches.subcommand_name() {
+        Some("shell") => shell::shell_command(&mut stine, &mut settings, &mut config),
+        Some("tui") => tui::tui_command(&mut stine, *matches.get_one::<Backend>("backend").unwrap()),
+        _ => run_command(&matches, &mut stine, &mut settings),
+    }
+
+
+    let session = Session {
+        session: stine.session.unwrap(),
+        cnsc_cookie: stine.cnsc_cookie.unwrap(),
+        last_used: None,
+    };
 
     if matches.get_flag("save_config") {
-        save_cfg(&CONFIG_PATH, &mut auth_cfg)
-            .with_context(|| format!("Failed saving config to {}", &CONFIG_PATH.display())).unwrap();
-        println!("{} [{}]",
-                 "> Saved credentials and session to config file".bright_green(),
-                 &CONFIG_PATH.display().to_string().underline());
+        let path = match &mut secret_store {
+            Some(secret_store) => {
+                secret_store.profile_mut(&profile_name).session = Some(Session {
+                    last_used: Some(Utc::now().timestamp()),
+                    ..session
+                });
+                secret_store.save().context("Failed saving session to the encrypted store").unwrap();
+                STORE_PATH.as_path()
+            }
+            None => {
+                config::save_session(&SESSIONS_PATH, &profile_name, session)
+                    .with_context(|| format!("Failed saving session to {}", &SESSIONS_PATH.display())).unwrap();
+                SESSIONS_PATH.as_path()
+            }
+        };
+        eprintln!("{} [{}]",
+                 "> Saved session of the profile".bright_green(),
+                 path.display().to_string().underline());
     }
 }
 
@@ -565,4 +817,56 @@ fn main() {
 #[test]
 fn verify_cmd() {
     get_command().debug_assert();
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_format_attempt() {
+    use stine_rs::mobile::StudentExamRaw;
+
+    let exam = |grade: &str, due_date: &str, semester: &str| StudentExamRaw {
+        exam_id: "108751472457".to_string(),
+        exam_name: "Klausur".to_string(),
+        context: "64-074 Berechenbarkeit".to_string(),
+        context_type: "course".to_string(),
+        subject: String::new(),
+        begin_date: String::new(),
+        due_date: due_date.to_string(),
+        time_from: String::new(),
+        time_to: String::new(),
+        grade: grade.to_string(),
+        grade_description: String::new(),
+        instructor_string: String::new(),
+        status: String::new(),
+        status_system: String::new(),
+        semester_id: String::new(),
+        semester_name: semester.to_string(),
+    };
+    // failed in the winter semester, passed in the following summer semester
+    let histories = ExamHistory::from_exams(vec![
+        exam("5,0", "15.02.2024", "WiSe 23/24").into(),
+        exam("2,3", "20.07.2024", "SuSe 24").into(),
+    ]);
+
+    let winter: SemesterStine = "WiSe 23/24".parse().unwrap();
+    let summer: SemesterStine = "SuSe 24".parse().unwrap();
+    assert_eq!(format_attempt(&histories, "64-074", &winter), "1");
+    assert_eq!(format_attempt(&histories, "64-074", &summer), "2 (retake)");
+    assert_eq!(format_attempt(&histories, "64-074", &"SuSe 23".parse().unwrap()), "-");
+    assert_eq!(format_attempt(&histories, "64-040", &summer), "-");
+}
+
+#[test]
+fn test_read_stdin_password() {
+    let matches = get_command()
+        .get_matches_from(["stine-cli", "--password-stdin", "notify", "--email-password-stdin"]);
+    let mut settings = Settings::resolve(&matches, &ConfigFile::default(), |_| None).unwrap();
+    let mut stdin = "stine-password\nemail-password\n".as_bytes();
+
+    read_stdin_password(&matches, &mut settings, &mut stdin).unwrap();
+    assert_eq!(settings.password.value.as_deref(), Some("stine-password"));
+
+    // the session is still valid, so the credentials are never requested
+    let (_, notify_matches) = matches.subcommand().unwrap();
+    settings.read_email_password(notify_matches.get_flag("email-password-stdin").then_some(&mut stdin), false).unwrap();
+    assert_eq!(settings.email_password.value.as_deref(), Some("email-password"));
+}
@@ -0,0 +1,260 @@
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::Context;
+use chrono::{DateTime, Local};
+use clap::{arg, ArgMatches, Command, value_parser};
+use colored::Colorize;
+use log::{error, info};
+use prettytable::{row, Table};
+
+use stine_rs::mobile::{Material, StudentEvent};
+use stine_rs::Stine;
+
+use crate::output;
+use crate::output::OutputFormat;
+
+// default directory for `materials sync`, relative to the current working dir
+const MATERIALS_PATH: &str = "./stine-materials";
+
+pub(crate) fn materials_subcommand() -> Command {
+    Command::new("materials")
+        .about("List and download the materials of your courses")
+        .subcommand_required(true)
+        .subcommands([
+            Command::new("list")
+                .about("List the materials of all your courses")
+                .arg(arg!(-c --course <COURSE_ID> "Only list materials of this course")
+                    .required(false)
+                    .value_parser(value_parser!(String))),
+            Command::new("sync")
+                .about("Download all materials into a directory per course. Skips files which are already up to date")
+                .arg(arg!(-d --dir <DIR> "Directory to download the materials to")
+                    .required(false)
+                    .default_value(MATERIALS_PATH)
+                    .value_parser(value_parser!(PathBuf)))
+                .arg(arg!(-c --course <COURSE_ID> "Only download materials of this course")
+                    .required(false)
+                    .value_parser(value_parser!(String))),
+        ])
+}
+
+// file name of materials and courses whose name is empty or a relative path like `..`
+const FALLBACK_FILE_NAME: &str = "unnamed";
+
+/// Replaces characters which are not allowed in file names on common file systems
+pub(crate) fn sanitize_file_name(name: &str) -> String {
+    let name = name.trim()
+        .replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_");
+    match name.as_str() {
+        "" | "." | ".." => FALLBACK_FILE_NAME.to_string(),
+        _ => name,
+    }
+}
+
+fn course_dir_name(event: &StudentEvent) -> String {
+    let name = match (&event.course_number, &event.course_name) {
+        (Some(number), Some(name)) => format!("{number} {name}"),
+        (None, Some(name)) => name.to_string(),
+        _ => event.course_id.clone().unwrap_or_default(),
+    };
+    sanitize_file_name(&name)
+}
+
+/// Returns the file names of the materials of a course.
+/// Materials sharing a name get their id appended, e.g. `Blatt (1234).pdf`, so they don't overwrite each other
+fn material_file_names(materials: &[Material]) -> Vec<String> {
+    let names: Vec<String> = materials.iter().map(|m| sanitize_file_name(&m.name)).collect();
+    names.iter().zip(materials)
+        .map(|(name, material)| {
+            if names.iter().filter(|n| *n == name).count() == 1 {
+                return name.clone();
+            }
+            let id = sanitize_file_name(&material.material_id);
+            match Path::new(name).extension().and_then(|ext| ext.to_str()) {
+                Some(ext) => format!("{} ({id}).{ext}", name.strip_suffix(&format!(".{ext}")).unwrap()),
+                None => format!("{name} ({id})"),
+            }
+        })
+        .collect()
+}
+
+/// Returns the events with materials, optionally filtered by `course_id`
+fn events_with_materials(stine: &Stine, course_id: Option<&String>) -> Vec<StudentEvent> {
+    let mut spinner = output::spinner("Fetching courses");
+    let events = stine.get_student_events()
+        .context("Failed fetching courses from the stine mobile app").unwrap();
+    output::stop_spinner(&mut spinner);
+
+    events.into_iter()
+        .filter(|e| e.material_present.unwrap_or_default() && e.course_id.is_some())
+        .filter(|e| course_id.is_none() || e.course_id.as_ref() == course_id)
+        .collect()
+}
+
+/// Checks if the local file has the same size as the material and is not older than it.
+/// Files of materials without size and date are never up to date, as there is nothing to compare
+fn is_up_to_date(path: &Path, material: &Material) -> bool {
+    let Ok(metadata) = fs::metadata(path) else {
+        return false;
+    };
+
+    if let Some(size) = material.size {
+        if metadata.len() != size {
+            return false;
+        }
+    }
+
+    match (material.datetime, metadata.modified()) {
+        (Some(datetime), Ok(modified)) => DateTime::<chrono::Utc>::from(modified) >= datetime,
+        (Some(_), Err(_)) => false,
+        (None, _) => material.size.is_some(),
+    }
+}
+
+fn download(stine: &Stine, material: &Material, path: &Path) -> anyhow::Result<()> {
+    let content = stine.download_material(material)?;
+    fs::write(path, content)?;
+
+    // use the upload date as modification date, so later syncs can compare them
+    if let Some(datetime) = material.datetime {
+        File::options().write(true).open(path)?
+            .set_modified(SystemTime::from(datetime))?;
+    }
+
+    Ok(())
+}
+
+fn sync(stine: &Stine, dir: &Path, course_id: Option<&String>) {
+    let (mut downloaded, mut skipped, mut failed) = (0, 0, 0);
+
+    for event in events_with_materials(stine, course_id) {
+        let course_dir = dir.join(course_dir_name(&event));
+        let materials = stine.get_materials(event.course_id.as_ref().unwrap())
+            .with_context(|| format!("Failed fetching materials of {}", course_dir.display())).unwrap();
+
+        fs::create_dir_all(&course_dir)
+            .with_context(|| format!("Failed creating directory {}", course_dir.display())).unwrap();
+
+        for (material, file_name) in materials.iter().zip(material_file_names(&materials)) {
+            let path = course_dir.join(file_name);
+
+            if is_up_to_date(&path, material) {
+                info!("Skipping up to date material: {}", path.display());
+                skipped += 1;
+                continue;
+            }
+
+            match download(stine, material, &path) {
+                Ok(_) => {
+                    println!("{} {}", "Downloaded".green(), path.display());
+                    downloaded += 1;
+                }
+                Err(err) => {
+                    error!("Failed downloading material {}: {err}", material.name);
+                    eprintln!("{} {}: {err}", "Failed downloading".red(), path.display());
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    println!("{} downloaded, {} up to date, {} failed [{}]",
+             downloaded.to_string().bright_green(), skipped, failed.to_string().red(),
+             dir.display().to_string().underline());
+}
+
+pub(crate) fn materials_command(sub_matches: &ArgMatches, stine: &Stine) {
+    match sub_matches.subcommand() {
+        Some(("list", list_matches)) => {
+            let course_id: Option<&String> = list_matches.get_one("course");
+
+            let mut table = Table::new();
+            let mut all_materials: Vec<Material> = vec![];
+            table.add_row(row!["Course", "Name", "Size", "Date"]);
+            for event in events_with_materials(stine, course_id) {
+                let materials = stine.get_materials(event.course_id.as_ref().unwrap())
+                    .context("Failed fetching materials").unwrap();
+                for material in materials {
+                    table.add_row(row![
+                        course_dir_name(&event),
+                        &material.name,
+                        material.size.map_or_else(|| "-".to_string(), |s| format!("{:.1} KB", s as f64 / 1024.0)),
+                        material.datetime.map_or_else(|| "-".to_string(),
+                            |dt| DateTime::<Local>::from(dt).format("%Y-%m-%d %H:%M").to_string()),
+                    ]);
+                    all_materials.push(material);
+                }
+            }
+            output::print_output(OutputFormat::from_matches(list_matches), &table, &all_materials);
+        }
+        Some(("sync", sync_matches)) => {
+            let dir: &PathBuf = sync_matches.get_one("dir").unwrap();
+            let course_id: Option<&String> = sync_matches.get_one("course");
+            sync(stine, dir, course_id);
+        }
+        _ => unimplemented!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use chrono::{Duration, Utc};
+
+    use stine_rs::mobile::Material;
+
+    use crate::materials::{is_up_to_date, material_file_names, sanitize_file_name};
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("64-074 Vorlesung BKA"), "64-074 Vorlesung BKA");
+        assert_eq!(sanitize_file_name(" Blatt 1/2: Lösung? "), "Blatt 1_2_ Lösung_");
+        assert_eq!(sanitize_file_name(" "), "unnamed");
+        assert_eq!(sanitize_file_name(".."), "unnamed");
+        assert_eq!(sanitize_file_name("."), "unnamed");
+    }
+
+    #[test]
+    fn test_is_up_to_date() {
+        let path = std::env::temp_dir().join("stine-cli-material-test.txt");
+        fs::write(&path, "Blatt 1").unwrap();
+
+        let mut material = material("11", "Blatt.txt");
+        assert!(!is_up_to_date(&path, &material));
+        assert!(!is_up_to_date(&path.with_extension("missing"), &material));
+
+        material.size = Some(7);
+        assert!(is_up_to_date(&path, &material));
+        material.size = Some(8);
+        assert!(!is_up_to_date(&path, &material));
+
+        material.size = None;
+        material.datetime = Some(Utc::now() - Duration::days(1));
+        assert!(is_up_to_date(&path, &material));
+        material.datetime = Some(Utc::now() + Duration::days(1));
+        assert!(!is_up_to_date(&path, &material));
+    }
+
+    fn material(material_id: &str, name: &str) -> Material {
+        Material {
+            material_id: material_id.to_string(),
+            name: name.to_string(),
+            size: None,
+            datetime: None,
+            course_id: "1".to_string(),
+            course_name: None,
+        }
+    }
+
+    #[test]
+    fn test_material_file_names() {
+        let materials = [material("11", "Blatt.pdf"), material("12", "Blatt.pdf"),
+            material("13", "Folien"), material("14", "Folien"), material("15", "Skript.pdf")];
+        assert_eq!(material_file_names(&materials),
+                   ["Blatt (11).pdf", "Blatt (12).pdf", "Folien (13)", "Folien (14)", "Skript.pdf"]);
+    }
+}
@@ -0,0 +1,119 @@
+use anyhow::Context;
+use chrono::{DateTime, Local};
+use clap::{arg, ArgAction, ArgMatches, Command, value_parser};
+use colored::Colorize;
+use prettytable::{row, Table};
+
+use stine_rs::mobile::Message;
+use stine_rs::Stine;
+
+use crate::output;
+use crate::output::OutputFormat;
+
+pub(crate) fn messages_subcommand() -> Command {
+    Command::new("messages")
+        .about("List, read and reply to the messages of your STINE inbox")
+        .subcommand_required(true)
+        .subcommands([
+            Command::new("list")
+                .about("List all messages")
+                .arg(arg!(-u --unread "Only list unread messages")
+                    .required(false)
+                    .action(ArgAction::SetTrue)),
+            Command::new("read")
+                .about("Print a message and mark it as read")
+                .arg(arg!(<ID> "Id of the message. See `messages list`")
+                    .value_parser(value_parser!(String))),
+            Command::new("reply")
+                .about("Reply to a message")
+                .arg(arg!(<ID> "Id of the message. See `messages list`")
+                    .value_parser(value_parser!(String)))
+                .arg(arg!(<TEXT> "Content of the reply")
+                    .value_parser(value_parser!(String))),
+            Command::new("delete")
+                .about("Delete a message from your inbox")
+                .arg(arg!(<ID> "Id of the message. See `messages list`")
+                    .value_parser(value_parser!(String))),
+        ])
+}
+
+fn format_datetime(datetime: Option<DateTime<chrono::Utc>>) -> String {
+    datetime.map_or_else(|| "-".to_string(),
+                         |dt| DateTime::<Local>::from(dt).format("%Y-%m-%d %H:%M").to_string())
+}
+
+fn fetch_messages(stine: &Stine) -> Vec<Message> {
+    let mut spinner = output::spinner("Fetching messages");
+    let messages = stine.get_messages()
+        .context("Failed fetching messages from the stine mobile app").unwrap();
+    output::stop_spinner(&mut spinner);
+
+    messages
+}
+
+fn find_message(stine: &Stine, id: &str) -> Option<Message> {
+    fetch_messages(stine).into_iter()
+        .find(|m| m.message_id == id)
+}
+
+pub(crate) fn messages_command(sub_matches: &ArgMatches, stine: &Stine) {
+    match sub_matches.subcommand() {
+        Some(("list", list_matches)) => {
+            let only_unread = list_matches.get_flag("unread");
+
+            let messages: Vec<Message> = fetch_messages(stine).into_iter()
+                .filter(|m| !only_unread || !m.is_read.unwrap_or_default())
+                .collect();
+
+            let mut table = Table::new();
+            table.add_row(row!["ID", "Date", "Sender", "Subject"]);
+            for message in &messages {
+                let subject = message.subject.clone().unwrap_or_default();
+                // highlight unread messages
+                let subject = if message.is_read.unwrap_or_default() { subject.normal() } else { subject.bold() };
+
+                table.add_row(row![
+                    message.message_id,
+                    format_datetime(message.datetime),
+                    message.sender.as_deref().unwrap_or("-"),
+                    subject,
+                ]);
+            }
+            output::print_output(OutputFormat::from_matches(list_matches), &table, &messages);
+        }
+        Some(("read", read_matches)) => {
+            let id: &String = read_matches.get_one("ID").unwrap();
+            let Some(message) = find_message(stine, id) else {
+                println!("{}", format!("No message with id {id} found").bright_red());
+                return;
+            };
+
+            println!("{} {}", "From:".bold(), message.sender.as_deref().unwrap_or("-"));
+            println!("{} {}", "Date:".bold(), format_datetime(message.datetime));
+            println!("{} {}", "Subject:".bold(), message.subject.as_deref().unwrap_or("-"));
+            println!();
+            println!("{}", message.content.as_deref().unwrap_or_default());
+
+            if !message.is_read.unwrap_or_default() {
+                stine.mark_message_read(&message.message_id)
+                    .context("Failed marking message as read").unwrap();
+            }
+        }
+        Some(("reply", reply_matches)) => {
+            let id: &String = reply_matches.get_one("ID").unwrap();
+            let text: &String = reply_matches.get_one("TEXT").unwrap();
+
+            stine.reply_message(id, text)
+                .context("Failed replying to message").unwrap();
+            println!("{}", "Reply sent".bright_green());
+        }
+        Some(("delete", delete_matches)) => {
+            let id: &String = delete_matches.get_one("ID").unwrap();
+
+            stine.delete_message(id)
+                .context("Failed deleting message").unwrap();
+            println!("{}", format!("Deleted message {id}").bright_green());
+        }
+        _ => unimplemented!(),
+    }
+}
@@ -2,9 +2,9 @@ use std::{env, fs, io};
 use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use chrono::Utc;
+use chrono::{DateTime, Local, Utc};
 use clap::{ArgMatches, ValueEnum};
 use if_chain::if_chain;
 use lazy_static::lazy_static;
@@ -18,15 +18,23 @@ use serde::de::DeserializeOwned;
 use thiserror::Error;
 
 use stine_rs::{CourseResult, Document, LazyLevel, MyRegistrations, RegistrationPeriod, SemesterResult, Stine};
-use stine_rs::mobile::{StudentExam, StudentExams};
+use stine_rs::mobile::{ExamHistory, StudentExam};
 
+use crate::config::Settings;
 use crate::Language;
 
 // path for comparison files
 const NOTIFY_PATH: &str = "./notify";
 
+/// Default directory of the notify data, next to the executable
+pub(crate) fn default_notify_dir() -> PathBuf {
+    // lots of unwraps in this line O_O
+    env::current_exe().unwrap().parent().unwrap().join(NOTIFY_PATH)
+}
+
 /// Events one can be notified for
-#[derive(ValueEnum, Debug, Clone, Copy)]
+#[derive(ValueEnum, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
 pub enum NotifyEvent {
     ExamResult,
     MobileExamResult,
@@ -95,12 +103,15 @@ impl EmailAuthConfig {
     }
 }
 
-/// Loads `EmailAuthConfig` from CLI Matches
-fn get_email_cfg(sub_matches: &ArgMatches) -> EmailAuthConfig {
-    let email_address: &String = sub_matches.get_one("email_address").unwrap();
-    let email_password: &String = sub_matches.get_one("email_password").unwrap();
-    let smtp_host: Option<&String> = sub_matches.get_one("smtp_server");
-    let smtp_port: Option<&u16> = sub_matches.get_one("smtp_port");
+/// Loads `EmailAuthConfig` from the resolved settings
+fn get_email_cfg(settings: &Settings) -> EmailAuthConfig {
+    let (Some(email_address), Some(email_password)) = (&settings.email_address.value, &settings.email_password.value) else {
+        error!("No email address or password set");
+        panic!("No email address or password set. Use --email_address and --email_password, \
+        STINE_EMAIL_ADDRESS and STINE_EMAIL_PASSWORD or the notify settings of the profile");
+    };
+    let smtp_host: Option<&String> = settings.smtp_server.value.as_ref();
+    let smtp_port: Option<&u16> = settings.smtp_port.value.as_ref();
 
     if_chain! {
         if let Some(host) = smtp_host;
@@ -125,15 +136,15 @@ fn get_email_cfg(sub_matches: &ArgMatches) -> EmailAuthConfig {
 }
 
 // TODO: rewrite using actions {EmailAction, PrintAction, SystemNotificationAction, ...}
-pub(crate) fn notify_command(sub_matches: &ArgMatches, stine: &mut Stine) {
-    let email_cfg = get_email_cfg(sub_matches);
+pub(crate) fn notify_command(sub_matches: &ArgMatches, stine: &mut Stine, settings: &Settings) {
+    let email_cfg = get_email_cfg(settings);
 
     if sub_matches.get_flag("send-test-email") {
         send_email("Stine Notifier - Test Email".to_string(), "Stine Notifier works :)".to_string(),
                    Vec::new(), &email_cfg);
         return;
     }
-    let mut events: Vec<NotifyEvent> = sub_matches.get_many("events").unwrap_or_default().copied().collect();
+    let mut events: Vec<NotifyEvent> = settings.notify_events.value.clone().unwrap_or_default();
 
     if events.is_empty() {
         events = vec![NotifyEvent::ExamResult,
@@ -156,8 +167,7 @@ pub(crate) fn notify_command(sub_matches: &ArgMatches, stine: &mut Stine) {
 
     info!("Selected Events: {events:#?}");
 
-    // lots of unwraps in this line O_O
-    let files_path = env::current_exe().unwrap().parent().unwrap().join(NOTIFY_PATH);
+    let files_path = settings.notify_dir.value.clone().unwrap_or_else(default_notify_dir);
 
     let notifications = events.iter().map(|event| {
         match event {
@@ -235,8 +245,7 @@ fn period_update(stine: &Stine, path: &Path, dry: bool) -> NotificationGroup {
         let datetime_now = Utc::now();
         let period = reg_period.period();
 
-        let new = datetime_now >= period.start && datetime_now <= period.end
-            && !send_periods.contains(reg_period);
+        let new = period.contains(&datetime_now) && !send_periods.contains(reg_period);
         if new {
             send_periods.insert(*reg_period);
         }
@@ -397,10 +406,12 @@ fn documents_update(stine: &Stine, path: &Path, dry: bool) -> NotificationGroup
     if let Ok(old_docs) = read_data::<Vec<Document>>(&file_path) {
         let (new, removed) = calc_changes(old_docs, current_documents.clone());
         for new_doc in new.clone() {
-            if let Ok(content) = download_document(stine, &new_doc) {
-                let content_type = ContentType::parse("application/pdf").unwrap();
-                let attachment = Attachment::new(new_doc.name)
-                    .body(content, content_type);
+            if let Ok(downloaded) = stine.download_document(&new_doc) {
+                let content_type = downloaded.metadata.content_type.as_deref()
+                    .and_then(|c| ContentType::parse(c).ok())
+                    .unwrap_or_else(|| ContentType::parse("application/pdf").unwrap());
+                let attachment = Attachment::new(downloaded.metadata.file_name.unwrap_or(new_doc.name))
+                    .body(downloaded.content, content_type);
 
                 attachments.push(attachment);
             } else {
@@ -430,11 +441,6 @@ fn documents_update(stine: &Stine, path: &Path, dry: bool) -> NotificationGroup
                                     attachments)
 }
 
-fn download_document(stine: &Stine, doc: &Document) -> Result<Vec<u8>, reqwest::Error> {
-    let response = stine.get(&doc.download).unwrap();
-    Ok(response.bytes()?.as_ref().to_vec())
-}
-
 struct Changes {
     changes: Vec<(String, Change<String>)>,
 }
@@ -527,29 +533,24 @@ fn mobile_exam_update(stine: &mut Stine,
     let data: Option<DataWrapper<HashMap<String, StudentExam>>> =
         load_data(path, file_name, arg_lang, overwrite_lang, stine);
 
-    fn map_mobile_exam_by_id(exams: StudentExams) -> HashMap<String, StudentExam> {
-        let mut exam_map: HashMap<String, StudentExam> = HashMap::new();
-
-        for exam in exams.exams {
-            exam_map.insert(exam.clone().exam_id, exam);
-        }
-
-        exam_map
-    }
-
-    let latest_data: StudentExams = stine.get_exams_mobile()
+    let latest_data: Vec<ExamHistory> = stine.get_exam_history()
         .expect("Failed fetching exams from the stine mobile app");
 
-    let latest_map = map_mobile_exam_by_id(latest_data);
-    // dbg!(&latest_map);
+    // attempts are mapped by their exam id, which doesn't change when an earlier attempt is found
+    let attempts: HashMap<String, u8> = latest_data.iter()
+        .flat_map(|h| &h.attempts)
+        .map(|a| (a.exam.exam_id.clone(), a.attempt))
+        .collect();
+    let latest_map = map_exams_by_id(latest_data.into_iter().flat_map(|h| h.attempts).map(|a| a.exam));
 
     let mut changes = vec![];
     let file_path = path.join(file_name);
     if file_path.exists() {
         let data = data.unwrap();
-        let old_map: HashMap<String, StudentExam> = data.data;
+        // older versions mapped later attempts by "<exam id>#<attempt>"
+        let old_map = map_exams_by_id(data.data.into_values());
 
-        changes = get_exam_changes_mobile(old_map, &latest_map);
+        changes = get_exam_changes_mobile(old_map, &latest_map, &attempts);
         debug!("Exam changes: {changes:#?}");
     } else {
         warn!("This seems to be the first check for new exams [{} does not exist]. Therefore you won't receive any notifications. \
@@ -561,6 +562,13 @@ fn mobile_exam_update(stine: &mut Stine,
     NotificationGroup::from_changes(Changes::new(changes), "Update in course results [mobile]", vec![])
 }
 
+/// Maps the exams by their id
+fn map_exams_by_id(exams: impl IntoIterator<Item=StudentExam>) -> HashMap<String, StudentExam> {
+    exams.into_iter()
+        .map(|exam| (exam.exam_id.clone(), exam))
+        .collect()
+}
+
 /// converts `SemesterResult` list to Map of `CourseResults` where
 ///     - key: CourseNumber
 ///     - value: `CourseResult`
@@ -600,55 +608,61 @@ impl<T> Change<T> {
     }
 }
 
-fn get_struct_diff<T: Serialize>(a: &T, b: &T) -> Result<Vec<(String, Change<String>)>, anyhow::Error> {
-    let json_a = serde_json::to_value(a)?;
-    let json_b = serde_json::to_value(b)?;
-
-    let mut changes = vec![];
+fn format_datetime(datetime: &DateTime<Utc>) -> String {
+    DateTime::<Local>::from(*datetime).format("%Y-%m-%d %H:%M").to_string()
+}
 
-    for (key, value) in json_a.as_object().unwrap() {
-        let old_value = json_b.get(key).unwrap();
-        if old_value != value {
-            let change = Change::new(
-                old_value.clone().to_string(),
-                value.clone().to_string());
-            changes.push((key.clone(), change));
-        }
+/// Adds a change labeled with `label`, if `old` and `new` differ
+fn push_field_change<T: PartialEq>(changes: &mut Vec<(String, Change<String>)>, label: &str,
+                                   old: Option<&T>, new: Option<&T>, format: impl Fn(&T) -> String) {
+    if old != new {
+        changes.push((label.to_string(), Change::new(
+            old.map_or_else(|| "N/A".to_string(), &format),
+            new.map_or_else(|| "N/A".to_string(), &format))));
     }
+}
 
-    Ok(changes)
+/// Compares the parsed fields of two exams.
+/// Differences in the formatting of the raw strings, like "2,3" and "2.3", are no change
+fn get_student_exam_diff(old: &StudentExam, new: &StudentExam) -> Vec<(String, Change<String>)> {
+    let mut changes = vec![];
+    push_field_change(&mut changes, "Begin date", old.begin_date.as_ref(), new.begin_date.as_ref(), ToString::to_string);
+    push_field_change(&mut changes, "Date", old.due_date.as_ref(), new.due_date.as_ref(), ToString::to_string);
+    push_field_change(&mut changes, "From", old.datetime_from.as_ref(), new.datetime_from.as_ref(), format_datetime);
+    push_field_change(&mut changes, "To", old.datetime_to.as_ref(), new.datetime_to.as_ref(), format_datetime);
+    push_field_change(&mut changes, "Grade", old.grade.as_ref(), new.grade.as_ref(), ToString::to_string);
+    push_field_change(&mut changes, "Status", old.status.as_ref(), new.status.as_ref(), ToString::to_string);
+    push_field_change(&mut changes, "Semester", old.semester.as_ref(), new.semester.as_ref(), ToString::to_string);
+    changes
 }
 
-fn get_exam_changes_mobile(old_map: HashMap<String, StudentExam>, new_map: &HashMap<String, StudentExam>)
+/// Compares exams mapped by their id. Later attempts are named with their number of `attempts`
+fn get_exam_changes_mobile(old_map: HashMap<String, StudentExam>, new_map: &HashMap<String, StudentExam>,
+                           attempts: &HashMap<String, u8>)
                            -> Vec<(String, Change<String>)> {
     let mut changes: Vec<(String, Change<String>)> = Vec::new();
 
-    for (course_number, exam) in new_map.clone() {
-        let name = exam.clone().context;
+    for (exam_id, exam) in new_map.clone() {
+        let name = match attempts.get(&exam_id) {
+            Some(attempt) if *attempt > 1 => format!("{} (attempt {attempt})", exam.context),
+            _ => exam.context.clone(),
+        };
 
-        if let Some(old_exam) = old_map.get(&course_number) {
+        if let Some(old_exam) = old_map.get(&exam_id) {
             // compare to old entry
-            for (key, change) in get_struct_diff(&exam, old_exam).unwrap() {
+            for (key, change) in get_student_exam_diff(old_exam, &exam) {
                 changes.push((format!("{name} - [{key}]"), change));
             }
-
-
-            // if old_exam.status != exam.status {
-            //     // print_change(&name, &old_course.status, &course.status);
-            //     changes.push((name.clone(),
-            //                   Change::new(old_exam.clone().status, exam.status)));
-            // }
-        } else if !exam.status.is_empty() &&
-            exam.status != "&nbsp;" {
+        } else if exam.status.is_some() {
             // if the exam/course entry is new send an change.
             // But only if the new data is not None or empty.
             // This is to prevent changes like: [Course name] - -> Final Grade: None.
             // Where the exam was added but without relevant info, so you don't? want the notification
             changes.push(
-                (exam.context,
+                (name,
                  Change::new(
                      "-".to_string(),
-                     format!("Final Grade: {:?} | Status: {}", exam.grade, exam.status))));
+                     format!("Final Grade: {} | Status: {}", unwrap_or_na(exam.grade), unwrap_or_na(exam.status)))));
         }
     }
 
@@ -818,6 +832,7 @@ fn build_email(subject: &str, body: String, email_address: &str, attachments: Ve
 #[cfg(test)]
 mod tests {
     use std::{assert_eq, dbg, env};
+    use std::collections::HashMap;
     use std::path::PathBuf;
 
     use lazy_static::lazy_static;
@@ -826,7 +841,9 @@ mod tests {
 
     use stine_rs::{Document, RegistrationPeriod, Stine};
 
-    use crate::notify::{build_email, documents_update, download_document, period_update, read_data, write_data};
+    use stine_rs::mobile::{ExamHistory, StudentExam, StudentExamRaw};
+
+    use crate::notify::{build_email, documents_update, get_exam_changes_mobile, map_exams_by_id, period_update, read_data, write_data};
 
     fn auth() -> Stine {
         dotenv::dotenv().ok();
@@ -853,6 +870,97 @@ mod tests {
         assert_eq!("test", data);
     }
 
+    #[test]
+    fn test_exam_changes_mobile() {
+        let exam = |due_date: &str, time_to: &str, grade: &str| StudentExamRaw {
+            exam_id: "108751472457".to_string(),
+            exam_name: "Klausur".to_string(),
+            context: "64-074 Berechenbarkeit".to_string(),
+            context_type: "course".to_string(),
+            subject: String::new(),
+            begin_date: String::new(),
+            due_date: due_date.to_string(),
+            time_from: "10:00".to_string(),
+            time_to: time_to.to_string(),
+            grade: grade.to_string(),
+            grade_description: String::new(),
+            instructor_string: String::new(),
+            status: "bestanden".to_string(),
+            status_system: "1".to_string(),
+            semester_id: String::new(),
+            semester_name: "WiSe 23/24".to_string(),
+        }.into();
+
+        let old = HashMap::from([("108751472457".to_string(), exam("15.02.2024", "12:00", "2,3"))]);
+        let unchanged = HashMap::from([("108751472457".to_string(), exam("15.02.2024", "12:00", "2,3"))]);
+        assert!(get_exam_changes_mobile(old.clone(), &unchanged, &HashMap::new()).is_empty());
+
+        // only the formatting of the raw strings differs
+        let reformatted = HashMap::from([("108751472457".to_string(), exam(" 15.02.2024", "12:00", "2.30"))]);
+        assert!(get_exam_changes_mobile(old.clone(), &reformatted, &HashMap::new()).is_empty());
+
+        let new = HashMap::from([("108751472457".to_string(), exam("16.02.2024", "13:00", "1,7"))]);
+        let changes = get_exam_changes_mobile(old, &new, &HashMap::new());
+        let mut keys: Vec<&str> = changes.iter().map(|(key, _)| key.as_str()).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, [
+            "64-074 Berechenbarkeit - [Date]",
+            "64-074 Berechenbarkeit - [From]",
+            "64-074 Berechenbarkeit - [Grade]",
+            "64-074 Berechenbarkeit - [To]",
+        ]);
+
+        let grade = &changes.iter().find(|(key, _)| key.ends_with("[Grade]")).unwrap().1;
+        assert_eq!((grade.old.as_str(), grade.new.as_str()), ("2.3", "1.7"));
+    }
+
+    #[test]
+    fn test_exam_attempt_keys() {
+        let exam = |exam_id: &str, due_date: &str, grade: &str| -> StudentExam {
+            StudentExamRaw {
+                exam_id: exam_id.to_string(),
+                exam_name: "Klausur".to_string(),
+                context: "64-074 Berechenbarkeit".to_string(),
+                context_type: "course".to_string(),
+                subject: String::new(),
+                begin_date: String::new(),
+                due_date: due_date.to_string(),
+                time_from: String::new(),
+                time_to: String::new(),
+                grade: grade.to_string(),
+                grade_description: String::new(),
+                instructor_string: String::new(),
+                status: "bestanden".to_string(),
+                status_system: String::new(),
+                semester_id: String::new(),
+                semester_name: String::new(),
+            }.into()
+        };
+
+        // stored by an older version, which mapped the second attempt by "<exam id>#2"
+        let stored = HashMap::from([
+            ("1".to_string(), exam("1", "15.02.2024", "5,0")),
+            ("2#2".to_string(), exam("2", "20.03.2024", "2,3")),
+        ]);
+        let old = map_exams_by_id(stored.into_values());
+        let mut keys: Vec<&String> = old.keys().collect();
+        keys.sort_unstable();
+        assert_eq!(keys, ["1", "2"]);
+
+        // an earlier attempt was found, which renumbers the others
+        let histories = ExamHistory::from_exams(vec![
+            exam("1", "15.02.2024", "5,0"), exam("2", "20.03.2024", "2,3"), exam("0", "10.10.2023", "5,0")]);
+        let attempts: HashMap<String, u8> = histories.iter().flat_map(|h| &h.attempts)
+            .map(|a| (a.exam.exam_id.clone(), a.attempt))
+            .collect();
+        let new = map_exams_by_id(histories.into_iter().flat_map(|h| h.attempts).map(|a| a.exam));
+
+        let changes = get_exam_changes_mobile(old, &new, &attempts);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].0, "64-074 Berechenbarkeit");
+        assert_eq!(changes[0].1.new, "Final Grade: 5.0 | Status: passed");
+    }
+
     // TODO: impl this
     // #[test]
     // fn test_exam_change() {
@@ -891,7 +999,7 @@ mod tests {
     fn build_attachment_email() {
         let documents = STINE.get_documents().unwrap();
         let d = documents.get(0).unwrap();
-        let content = download_document(&STINE, d).unwrap();
+        let content = STINE.download_document(d).unwrap().content;
         let attachs = vec![
             Attachment::new("aaa.pdf".to_string()).
                 body(content, ContentType::parse("application/pdf").unwrap())
@@ -0,0 +1,155 @@
+use std::io;
+use std::sync::OnceLock;
+
+use clap::{Arg, ArgAction, ArgMatches, ValueEnum};
+use prettytable::Table;
+use serde::{Deserialize, Serialize};
+use spinners::{Spinner, Spinners, Stream};
+
+/// Id of the global `--output` flag
+pub(crate) const FORMAT_ARG: &str = "format";
+
+// format used if `--output` is not passed, e.g. from the selected profile
+static DEFAULT_FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Output format of the commands, set by the global `--output` flag
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum OutputFormat {
+    /// Colorized tables for humans
+    #[default]
+    Table,
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl OutputFormat {
+    pub(crate) fn from_matches(matches: &ArgMatches) -> Self {
+        if json_alias(matches) {
+            return OutputFormat::Json;
+        }
+        matches.get_one::<OutputFormat>(FORMAT_ARG).copied()
+            .or_else(|| DEFAULT_FORMAT.get().copied())
+            .unwrap_or_default()
+    }
+
+    /// Sets the format used if `--output` is not passed. Can only be set once
+    pub(crate) fn set_default(format: OutputFormat) {
+        DEFAULT_FORMAT.set(format).ok();
+    }
+
+    pub(crate) fn is_table(self) -> bool {
+        self == OutputFormat::Table
+    }
+}
+
+/// `--json` of commands, which supported json before `--output` existed. See [`json_flag`]
+fn json_alias(matches: &ArgMatches) -> bool {
+    matches!(matches.try_get_one::<bool>("json"), Ok(Some(true)))
+        || matches.subcommand().is_some_and(|(_, sub_matches)| json_alias(sub_matches))
+}
+
+/// The old `--json` flag, kept as alias of `--output json`
+pub(crate) fn json_flag() -> Arg {
+    Arg::new("json").long("json")
+        .action(ArgAction::SetTrue)
+        .conflicts_with(FORMAT_ARG)
+        .help("Alias of --output json")
+}
+
+/// Spinner writing to stderr, so it doesn't end up in piped output
+pub(crate) fn spinner(message: &str) -> Spinner {
+    Spinner::with_stream(Spinners::Dots, message.into(), Stream::Stderr)
+}
+
+/// Stops the spinner and moves to the next line
+pub(crate) fn stop_spinner(spinner: &mut Spinner) {
+    spinner.stop();
+    eprintln!();
+}
+
+fn escape_markdown(content: &str) -> String {
+    content.replace('|', "\\|").replace('\n', "<br>")
+}
+
+/// Renders the table as markdown. The first row is used as header
+fn to_markdown(table: &Table) -> String {
+    let rows: Vec<Vec<String>> = table.row_iter()
+        .map(|row| row.iter().map(|cell| escape_markdown(&cell.get_content())).collect())
+        .collect();
+
+    let Some(header) = rows.first() else {
+        return String::new();
+    };
+
+    let mut markdown = format!("| {} |\n", header.join(" | "));
+    markdown += &format!("|{}\n", " --- |".repeat(header.len()));
+    for row in rows.iter().skip(1) {
+        markdown += &format!("| {} |\n", row.join(" | "));
+    }
+    markdown
+}
+
+/// Prints `data` as json, or the `table` in the other formats
+pub(crate) fn print_output<T: Serialize + ?Sized>(format: OutputFormat, table: &Table, data: &T) {
+    match format {
+        OutputFormat::Table => {
+            table.printstd();
+        }
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(data).expect("Failed serializing output to json"));
+        }
+        OutputFormat::Csv => {
+            table.to_csv(io::stdout()).expect("Failed writing csv");
+        }
+        OutputFormat::Markdown => {
+            print!("{}", to_markdown(table));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prettytable::{row, Table};
+
+    use crate::get_command;
+    use crate::output::{OutputFormat, to_markdown};
+
+    #[test]
+    fn test_to_markdown() {
+        let mut table = Table::new();
+        table.add_row(row!["Name", "Grade"]);
+        table.add_row(row!["64-074 A|B", "1.0"]);
+
+        assert_eq!(to_markdown(&table), "| Name | Grade |\n| --- | --- |\n| 64-074 A\\|B | 1.0 |\n");
+        assert_eq!(to_markdown(&Table::new()), "");
+    }
+
+    #[test]
+    fn test_old_output_flags() {
+        let format = |args: &[&str]| {
+            let matches = get_command().try_get_matches_from(args).unwrap();
+            OutputFormat::from_matches(matches.subcommand().unwrap().1)
+        };
+        assert_eq!(format(&["stine-cli", "periods", "--json"]), OutputFormat::Json);
+        assert_eq!(format(&["stine-cli", "periods", "--output", "csv"]), OutputFormat::Csv);
+        assert!(get_command().try_get_matches_from(["stine-cli", "periods", "--json", "--output", "csv"]).is_err());
+
+        let matches = get_command().try_get_matches_from(["stine-cli", "documents", "list", "--json"]).unwrap();
+        assert_eq!(OutputFormat::from_matches(&matches), OutputFormat::Json);
+
+        for flag in ["-f", "--file"] {
+            let matches = get_command()
+                .try_get_matches_from(["stine-cli", "documents", "get", "0", flag, "certificate.pdf"]).unwrap();
+            let (_, get_matches) = matches.subcommand().unwrap().1.subcommand().unwrap();
+            assert_eq!(get_matches.get_one::<std::path::PathBuf>("file").unwrap().to_str(), Some("certificate.pdf"));
+        }
+
+        // --output selects the format in every command
+        let matches = get_command()
+            .try_get_matches_from(["stine-cli", "documents", "get", "0", "--output", "json"]).unwrap();
+        let (_, get_matches) = matches.subcommand().unwrap().1.subcommand().unwrap();
+        assert_eq!(OutputFormat::from_matches(get_matches), OutputFormat::Json);
+    }
+}
@@ -0,0 +1,110 @@
+use std::io::BufRead;
+use std::process::Command;
+
+use anyhow::{anyhow, Context};
+use log::info;
+
+use crate::config::{Setting, Source};
+
+/// Runs `command` using the shell and returns the first line of its output,
+/// e.g. `pass show stine`
+pub(crate) fn run_password_command(command: &str) -> anyhow::Result<String> {
+    let output = if cfg!(windows) {
+        Command::new("cmd").args(["/C", command]).output()
+    } else {
+        Command::new("sh").args(["-c", command]).output()
+    }.with_context(|| format!("Failed running password command `{command}`"))?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Password command `{command}` failed with {}: {}",
+            output.status, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    let stdout = String::from_utf8(output.stdout).context("Output of the password command is not valid utf-8")?;
+    Ok(stdout.lines().next().unwrap_or_default().to_string())
+}
+
+/// Reads one line of stdin, without the line break
+fn read_stdin_line(stdin: &mut dyn BufRead) -> anyhow::Result<String> {
+    let mut line = String::new();
+    stdin.read_line(&mut line).context("Failed reading password from stdin")?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Resolves a secret like a password. Uses the first available of:
+/// 1. the value passed as flag or already read from stdin
+/// 2. a line of `stdin`, if given
+/// 3. the value or command of the highest prioritized other source. Commands win on ties
+/// 4. a hidden prompt, if `interactive` is set
+pub(crate) fn resolve_secret(secret: &Setting<String>, command: &Setting<String>,
+                             stdin: Option<&mut dyn BufRead>, interactive: bool, prompt: &str) -> anyhow::Result<Setting<String>> {
+    if secret.value.is_some() && matches!(secret.source, Source::Flag(_) | Source::Stdin) {
+        return Ok(secret.clone());
+    }
+
+    if let Some(stdin) = stdin {
+        return Ok(Setting { value: Some(read_stdin_line(stdin)?), source: Source::Stdin });
+    }
+
+    if let Some(cmd) = &command.value {
+        if secret.value.is_none() || command.source.priority() >= secret.source.priority() {
+            info!("Reading password using the password command of {}", command.source);
+            return Ok(Setting { value: Some(run_password_command(cmd)?), source: Source::Command });
+        }
+    }
+
+    if secret.value.is_some() {
+        return Ok(secret.clone());
+    }
+
+    if interactive {
+        let password = rpassword::prompt_password(prompt).context("Failed reading password")?;
+        return Ok(Setting { value: Some(password), source: Source::Prompt });
+    }
+
+    Ok(secret.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{Setting, Source};
+    use crate::password::{resolve_secret, run_password_command};
+
+    fn setting(value: Option<&str>, source: Source) -> Setting<String> {
+        Setting { value: value.map(str::to_string), source }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_run_password_command() {
+        assert_eq!(run_password_command("printf 'hunter2\\nurl: stine.uni-hamburg.de'").unwrap(), "hunter2");
+        assert_eq!(run_password_command("true").unwrap(), "");
+        assert!(run_password_command("exit 1").is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_resolve_secret() {
+        let command = setting(Some("echo from-command"), Source::ConfigFile);
+        let none = setting(None, Source::Default);
+
+        let flag = setting(Some("from-flag"), Source::Flag("--password"));
+        assert_eq!(resolve_secret(&flag, &command, None, false, "").unwrap(), flag);
+
+        let env = setting(Some("from-env"), Source::Env("STINE_PASSWORD"));
+        assert_eq!(resolve_secret(&env, &command, None, false, "").unwrap(), env);
+
+        let file = setting(Some("from-file"), Source::ConfigFile);
+        assert_eq!(resolve_secret(&file, &command, None, false, "").unwrap(),
+                   setting(Some("from-command"), Source::Command));
+        assert_eq!(resolve_secret(&file, &none, None, false, "").unwrap(), file);
+
+        assert_eq!(resolve_secret(&none, &none, None, false, "").unwrap(), none);
+
+        let mut stdin = "from-stdin\r\nsecond line\n".as_bytes();
+        let from_stdin = resolve_secret(&file, &command, Some(&mut stdin), false, "").unwrap();
+        assert_eq!(from_stdin, setting(Some("from-stdin"), Source::Stdin));
+        // a password read from stdin isn't replaced by the password command later on
+        assert_eq!(resolve_secret(&from_stdin, &command, None, false, "").unwrap(), from_stdin);
+    }
+}
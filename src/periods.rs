@@ -0,0 +1,106 @@
+use anyhow::Context;
+use chrono::{DateTime, Duration, Local, Utc};
+use clap::{ArgMatches, Command};
+use colored::Colorize;
+use prettytable::{row, Table};
+use serde::Serialize;
+
+use stine_rs::{RegistrationPeriod, Stine};
+
+use crate::output;
+use crate::output::OutputFormat;
+
+pub(crate) fn periods_subcommand() -> Command {
+    Command::new("periods")
+        .about("Print all registration periods and the time until they start or end")
+        .arg(output::json_flag())
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum PeriodStatus {
+    Upcoming,
+    Active,
+    Over,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct PeriodEntry {
+    pub name: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub status: PeriodStatus,
+}
+
+impl PeriodEntry {
+    pub(crate) fn new(registration_period: &RegistrationPeriod, now: DateTime<Utc>) -> Self {
+        let period = registration_period.period();
+        let status = if period.contains(&now) {
+            PeriodStatus::Active
+        } else if now < period.start {
+            PeriodStatus::Upcoming
+        } else {
+            PeriodStatus::Over
+        };
+
+        PeriodEntry {
+            name: registration_period.name(),
+            start: period.start,
+            end: period.end,
+            status,
+        }
+    }
+}
+
+/// Formats durations like: 3d 4h 12m
+pub(crate) fn format_duration(duration: Duration) -> String {
+    let days = duration.num_days();
+    let hours = duration.num_hours() % 24;
+    let minutes = duration.num_minutes() % 60;
+
+    match (days, hours) {
+        (0, 0) => format!("{minutes}m"),
+        (0, _) => format!("{hours}h {minutes}m"),
+        _ => format!("{days}d {hours}h {minutes}m"),
+    }
+}
+
+pub(crate) fn periods_command(sub_matches: &ArgMatches, stine: &Stine) {
+    let mut spinner = output::spinner("Fetching registration periods");
+    let periods = stine.get_registration_periods()
+        .context("Failed fetching registration periods").unwrap();
+    output::stop_spinner(&mut spinner);
+
+    let now = Utc::now();
+    let entries: Vec<PeriodEntry> = periods.iter().map(|p| PeriodEntry::new(p, now)).collect();
+
+    let format_datetime = |dt: DateTime<Utc>| DateTime::<Local>::from(dt).format("%a %Y-%m-%d %H:%M").to_string();
+
+    let mut table = Table::new();
+    table.add_row(row!["Period", "Start", "End", "Status"]);
+    for entry in &entries {
+        let status = match entry.status {
+            PeriodStatus::Upcoming => format!("starts in {}", format_duration(entry.start - now)).yellow(),
+            PeriodStatus::Active => format!("current, ends in {}", format_duration(entry.end - now)).green().bold(),
+            PeriodStatus::Over => "over".dimmed(),
+        };
+        let name = if entry.status == PeriodStatus::Active { entry.name.bold() } else { entry.name.normal() };
+
+        table.add_row(row![name, format_datetime(entry.start), format_datetime(entry.end), status]);
+    }
+    output::print_output(OutputFormat::from_matches(sub_matches), &table, &entries);
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use crate::periods::format_duration;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::minutes(5)), "5m");
+        assert_eq!(format_duration(Duration::minutes(125)), "2h 5m");
+        assert_eq!(format_duration(Duration::days(3) + Duration::hours(4) + Duration::minutes(12)), "3d 4h 12m");
+    }
+}
@@ -0,0 +1,161 @@
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use regex::Regex;
+
+const MASK: &str = "***";
+
+// secrets only known at runtime, e.g. the password or the session of the current user
+static SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+lazy_static! {
+    /// Patterns of secrets in log messages and their replacement
+    static ref PATTERNS: Vec<(Regex, String)> = vec![
+        // key value pairs, e.g. `password: "hunter2"`, `cnsc=abc` or `session = "123"`
+        (Regex::new(r#"(?i)\b(email_password|password|passwd|passphrase|pass|cnsc_cookie|cnsc|session_id|session)("?\s*[:=]\s*"?)[^\s"',;&)]+"#).unwrap(),
+         format!("${{1}}${{2}}{MASK}")),
+        // form parameters in debug output, e.g. `("pass", "hunter2")`
+        (Regex::new(r#"(\("(?:pass|password|usrname)",\s*")[^"]*"#).unwrap(),
+         format!("${{1}}{MASK}")),
+        // session id, which is the first argument of every request, e.g. `PRGNAME -N123456789012345,-N000311`
+        (Regex::new(r#"(ARGUMENTS=|\b[A-Z_]{3,} |\[\s*")-N\d+"#).unwrap(), format!("${{1}}-N{MASK}")),
+        // encrypted arguments of the mobile api, which contain the session
+        (Regex::new(r"-A[A-Za-z0-9+/=%_-]{8,}").unwrap(), format!("-A{MASK}")),
+        // smtp authentication
+        (Regex::new(r"(?i)(AUTH (?:PLAIN|LOGIN) )\S+").unwrap(), format!("${{1}}{MASK}")),
+    ];
+}
+
+/// Registers a secret, which is masked in all following log records. Very short values are ignored,
+/// as they would mask unrelated parts of the messages
+pub(crate) fn add_secret(secret: &str) {
+    if secret.len() < 4 {
+        return;
+    }
+    let mut secrets = SECRETS.write().unwrap();
+    if !secrets.iter().any(|s| s == secret) {
+        secrets.push(secret.to_string());
+        // replace longer secrets first, in case a secret contains another one
+        secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+    }
+}
+
+/// Masks session ids, cookies, passwords and registered secrets in `message`
+pub(crate) fn redact(message: &str) -> String {
+    let mut redacted = message.to_string();
+    for secret in SECRETS.read().unwrap().iter() {
+        redacted = redacted.replace(secret.as_str(), MASK);
+    }
+    for (pattern, replacement) in PATTERNS.iter() {
+        redacted = pattern.replace_all(&redacted, replacement.as_str()).into_owned();
+    }
+    redacted
+}
+
+/// Logger redacting every record before passing it on to the wrapped logger
+pub(crate) struct RedactingLogger {
+    inner: Box<dyn Log>,
+}
+
+impl RedactingLogger {
+    pub(crate) fn new(inner: Box<dyn Log>) -> Self {
+        RedactingLogger { inner }
+    }
+
+    /// Installs the redacting logger as global logger
+    pub(crate) fn init(inner: Box<dyn Log>, max_level: LevelFilter) -> Result<(), SetLoggerError> {
+        log::set_boxed_logger(Box::new(RedactingLogger::new(inner)))?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+}
+
+impl Log for RedactingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let message = redact(&record.args().to_string());
+        self.inner.log(&Record::builder()
+            .metadata(record.metadata().clone())
+            .args(format_args!("{message}"))
+            .key_values(record.key_values())
+            .module_path(record.module_path())
+            .file(record.file())
+            .line(record.line())
+            .build());
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use log::{Level, Log, Metadata, Record};
+
+    use crate::redact::{add_secret, redact, RedactingLogger};
+
+    struct CapturingLogger(Arc<Mutex<Vec<String>>>);
+
+    impl Log for CapturingLogger {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn test_redact_patterns() {
+        assert_eq!(redact("POST to: COURSERESULTS -N123456789012345,-N000000015178000"),
+                   "POST to: COURSERESULTS -N***,-N000000015178000");
+        assert_eq!(redact("GET to: https://stine.uni-hamburg.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=COURSEDETAILS&ARGUMENTS=-N123456789012345,-N000311,-N382005035451473"),
+                   "GET to: https://stine.uni-hamburg.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=COURSEDETAILS&ARGUMENTS=-N***,-N000311,-N382005035451473");
+        assert_eq!(redact("Arguments: [\n    \"-N123456789012345\",\n    \"-N000311\",\n]"),
+                   "Arguments: [\n    \"-N***\",\n    \"-N000311\",\n]");
+        assert_eq!(redact("Arguments: -AbG9naW4gc2Vzc2lvbg=="), "Arguments: -A***");
+        assert_eq!(redact("Cookie: cnsc=ABCDEF0123456789; path=/"), "Cookie: cnsc=***; path=/");
+        assert_eq!(redact(r#"Session { session: "123", cnsc_cookie: "abc", last_used: None }"#),
+                   r#"Session { session: "***", cnsc_cookie: "***", last_used: None }"#);
+        assert_eq!(redact(r#"EmailAuthConfig { email_address: "a@b.de", email_password: "hunter2" }"#),
+                   r#"EmailAuthConfig { email_address: "a@b.de", email_password: "***" }"#);
+        assert_eq!(redact(r#"{("usrname", "baa1234"), ("pass", "hunter2"), ("APPNAME", "CampusNet")}"#),
+                   r#"{("usrname", "***"), ("pass", "***"), ("APPNAME", "CampusNet")}"#);
+        assert_eq!(redact("password=hunter2&APPNAME=CampusNet"), "password=***&APPNAME=CampusNet");
+        assert_eq!(redact("C: AUTH PLAIN AGJhYTEyMzQAaHVudGVyMg=="), "C: AUTH PLAIN ***");
+        assert_eq!(redact("Using saved session cookies"), "Using saved session cookies");
+    }
+
+    #[test]
+    fn test_redacting_logger() {
+        add_secret("correct horse battery");
+        add_secret("987654321098765");
+        add_secret("abc");
+
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let logger = RedactingLogger::new(Box::new(CapturingLogger(messages.clone())));
+        logger.log(&Record::builder()
+            .level(Level::Debug)
+            .target("stine_rs::stine")
+            .args(format_args!("Authenticating with correct horse battery, session {}", 987654321098765u64))
+            .build());
+
+        assert_eq!(messages.lock().unwrap().as_slice(), ["Authenticating with ***, session ***"]);
+        // too short to be registered
+        assert_eq!(redact("abc"), "abc");
+    }
+}
@@ -0,0 +1,306 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+
+use clap::{ArgMatches, Command};
+use clap::parser::ValueSource;
+use colored::Colorize;
+use lazy_static::lazy_static;
+use log::warn;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::FileHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+use stine_rs::Stine;
+
+use crate::config::{ConfigFile, Settings};
+use crate::output::OutputFormat;
+use crate::{Backend, completions, config, debug, get_command, run_command, tui};
+
+lazy_static! {
+    /// History of the commands entered in the shell
+    static ref HISTORY_PATH: PathBuf = dirs::data_dir().unwrap().join("stine-cli").join("shell_history.txt");
+}
+
+/// Commands of the shell itself
+const BUILTINS: [&str; 3] = ["help", "exit", "quit"];
+
+/// Login options, which only apply when starting the shell
+const LOGIN_ARGS: [&str; 6] = ["username", "password", "password-stdin", "save_config", "profile", "language"];
+
+pub(crate) fn shell_subcommand() -> Command {
+    Command::new("shell")
+        .about("Start an interactive shell, which accepts all other commands. \
+        Authenticates once and keeps fetched courses and modules in memory")
+}
+
+/// Tab completion of subcommands, flags and their values
+struct ShellHelper {
+    command: Command,
+}
+
+/// Completes the last word of `line`. Returns the start of the completed word and the candidates
+fn complete_line(command: &Command, line: &str) -> (usize, Vec<String>) {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (word, previous) = if line.ends_with(char::is_whitespace) || words.is_empty() {
+        ("", &words[..])
+    } else {
+        (words[words.len() - 1], &words[..words.len() - 1])
+    };
+
+    // the deepest subcommand of the previous words
+    let mut current = command;
+    for previous_word in previous {
+        if let Some(subcommand) = current.find_subcommand(previous_word) {
+            current = subcommand;
+        }
+    }
+
+    // values of the previous flag, e.g. `--output j`
+    let previous_arg = previous.last()
+        .and_then(|flag| flag.strip_prefix("--"))
+        .and_then(|long| current.get_arguments().chain(command.get_arguments())
+            .find(|arg| arg.get_long() == Some(long)));
+
+    let mut candidates: Vec<String> = if let Some(arg) = previous_arg.filter(|arg| arg.get_action().takes_values()) {
+        arg.get_possible_values().iter().map(|value| value.get_name().to_string()).collect()
+    } else if word.starts_with('-') {
+        current.get_arguments()
+            .chain(command.get_arguments().filter(|arg| arg.is_global_set()))
+            .filter_map(|arg| arg.get_long())
+            .map(|long| format!("--{long}"))
+            .collect()
+    } else {
+        let builtins = previous.is_empty().then_some(BUILTINS.iter().copied()).into_iter().flatten();
+        current.get_subcommands()
+            .map(Command::get_name)
+            .filter(|name| *name != "shell" && *name != "help")
+            .chain(builtins)
+            .map(str::to_string)
+            .collect()
+    };
+
+    candidates.retain(|candidate| candidate.starts_with(word));
+    candidates.sort();
+    candidates.dedup();
+    (line.len() - word.len(), candidates)
+}
+
+impl Completer for ShellHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete_line(&self.command, &line[..pos]))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
+
+/// Parses a line of the shell like the arguments of the cli, e.g. `course show "64-040 Einführung"`
+fn parse_line(line: &str) -> Result<Option<ArgMatches>, String> {
+    let words = shlex::split(line).ok_or("Unmatched quotes")?;
+    if words.is_empty() {
+        return Ok(None);
+    }
+    get_command()
+        .no_binary_name(true)
+        .try_get_matches_from(words)
+        .map(Some)
+        .map_err(|err| err.render().ansi().to_string())
+}
+
+/// Flags whose value is a secret
+const SECRET_FLAGS: [&str; 2] = ["--password", "--email_password"];
+
+/// Checks if the line passes a secret flag, e.g. `--password hunter2` or `--email_password=hunter2`
+fn contains_secret(line: &str) -> bool {
+    let words = shlex::split(line)
+        .unwrap_or_else(|| line.split_whitespace().map(ToString::to_string).collect());
+    words.iter().any(|word| SECRET_FLAGS.iter()
+        .any(|flag| word == flag || word.strip_prefix(flag).is_some_and(|rest| rest.starts_with('='))))
+}
+
+/// Runs the command of a line. Returns false, if the shell should exit
+fn run_line(line: &str, stine: &mut Stine, settings: &mut Settings, config: &mut ConfigFile) -> bool {
+    match line.trim() {
+        "" => return true,
+        "exit" | "quit" => return false,
+        "help" => {
+            get_command().no_binary_name(true).print_help().ok();
+            return true;
+        }
+        _ => {}
+    }
+
+    let matches = match parse_line(line) {
+        Ok(Some(matches)) => matches,
+        Ok(None) => return true,
+        Err(err) => {
+            eprint!("{err}");
+            return true;
+        }
+    };
+
+    for id in LOGIN_ARGS {
+        if matches.value_source(id) == Some(ValueSource::CommandLine) {
+            warn!("--{id} is ignored in the shell. Pass it when starting the shell instead");
+        }
+    }
+
+    if OutputFormat::from_matches(&matches).is_table() {
+        colored::control::unset_override();
+    } else {
+        colored::control::set_override(false);
+    }
+
+    // commands panic or return errors, which must not end the shell
+    let result = panic::catch_unwind(AssertUnwindSafe(|| match matches.subcommand() {
+        Some(("shell", _)) => {
+            eprintln!("{}", "Already running the shell".yellow());
+            Ok(())
+        }
+        Some(("config", sub_matches)) => {
+            config::config_command(sub_matches, settings, config);
+            Ok(())
+        }
+        Some(("debug", sub_matches)) => debug::debug_command(sub_matches),
+        Some(("completions", sub_matches)) => {
+            let cache_dir = settings.cache_dir.value.as_ref().unwrap();
+            completions::completions_command(sub_matches, get_command(), cache_dir)
+        }
+        Some(("man", sub_matches)) => completions::man_command(sub_matches, get_command()),
+        Some(("tui", _)) => {
+            tui::tui_command(stine, *matches.get_one::<Backend>("backend").unwrap());
+            Ok(())
+        }
+        _ => {
+            run_command(&matches, stine, settings);
+            Ok(())
+        }
+    }));
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => eprintln!("{}", format!("{err:#}").red()),
+        Err(_) => eprintln!("{}", "Command failed".red()),
+    }
+    true
+}
+
+/// Reads and runs commands until `exit` or Ctrl-D, reusing `stine`
+pub(crate) fn shell_command(stine: &mut Stine, settings: &mut Settings, config: &mut ConfigFile) {
+    let mut editor: Editor<ShellHelper, FileHistory> = match Editor::new() {
+        Ok(editor) => editor,
+        Err(err) => {
+            eprintln!("{}", format!("Failed starting the shell: {err}").red());
+            return;
+        }
+    };
+    editor.set_helper(Some(ShellHelper { command: get_command() }));
+    if HISTORY_PATH.exists() {
+        if let Err(err) = editor.load_history(HISTORY_PATH.as_path()) {
+            warn!("Failed loading shell history from {}: {err}", HISTORY_PATH.display());
+        }
+    }
+
+    eprintln!("Type a command like {} or {}. Use tab for completion and {} to leave",
+              "courses".bold(), "semester-results".bold(), "exit".bold());
+    let prompt = format!("stine [{}]> ", settings.profile_name());
+
+    loop {
+        match editor.readline(&prompt) {
+            Ok(line) => {
+                // don't keep passwords in the history
+                if !contains_secret(&line) {
+                    editor.add_history_entry(line.as_str()).ok();
+                }
+                if !run_line(&line, stine, settings, config) {
+                    break;
+                }
+            }
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                eprintln!("{}", format!("Failed reading line: {err}").red());
+                break;
+            }
+        }
+    }
+
+    if let Some(parent) = HISTORY_PATH.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    if let Err(err) = editor.save_history(HISTORY_PATH.as_path()) {
+        warn!("Failed saving shell history to {}: {err}", HISTORY_PATH.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::get_command;
+    use crate::shell::{complete_line, contains_secret, parse_line};
+
+    #[test]
+    fn test_complete_line() {
+        let command = get_command();
+
+        let (start, candidates) = complete_line(&command, "cou");
+        assert_eq!(start, 0);
+        assert_eq!(candidates, vec!["course", "courses"]);
+
+        assert!(complete_line(&command, "").1.contains(&"exit".to_string()));
+        assert!(!complete_line(&command, "").1.contains(&"shell".to_string()));
+
+        let (start, candidates) = complete_line(&command, "documents l");
+        assert_eq!(start, 10);
+        assert_eq!(candidates, vec!["list"]);
+
+        let (_, candidates) = complete_line(&command, "semester-results --gr");
+        assert_eq!(candidates, vec!["--grade-avg"]);
+
+        // global flags are available in every subcommand
+        let (_, candidates) = complete_line(&command, "courses --outp");
+        assert_eq!(candidates, vec!["--output"]);
+
+        let (start, candidates) = complete_line(&command, "courses --output j");
+        assert_eq!(start, 17);
+        assert_eq!(candidates, vec!["json"]);
+    }
+
+    #[test]
+    fn test_contains_secret() {
+        assert!(contains_secret("--password hunter2 courses"));
+        assert!(contains_secret("notify --email_password=hunter2"));
+        assert!(contains_secret("notify --email_password \"unterminated"));
+        assert!(!contains_secret("config show password_command"));
+        assert!(!contains_secret("notify --email-password-stdin"));
+        assert!(!contains_secret("course show \"64-040 Passwords\""));
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let matches = parse_line("semester-results -s \"wise 21/22\" --grade-avg").unwrap().unwrap();
+        let (name, sub_matches) = matches.subcommand().unwrap();
+        assert_eq!(name, "semester-results");
+        assert!(sub_matches.get_flag("grade-avg"));
+
+        assert!(parse_line("  ").unwrap().is_none());
+        assert!(parse_line("course \"unterminated").is_err());
+        assert!(parse_line("unknown-command").is_err());
+
+        let matches = parse_line("debug decode-args -AbG9naW4").unwrap().unwrap();
+        let (_, debug_matches) = matches.subcommand().unwrap();
+        let (_, decode_matches) = debug_matches.subcommand().unwrap();
+        assert_eq!(decode_matches.get_one::<String>("URL").unwrap(), "-AbG9naW4");
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use age::secrecy::{ExposeSecret, SecretString};
+use anyhow::{anyhow, Context};
+use lazy_static::lazy_static;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{ConfigFile, Session, Settings};
+use crate::password::run_password_command;
+
+lazy_static! {
+    /// Encrypted passwords and sessions of all profiles
+    pub(crate) static ref STORE_PATH: PathBuf = dirs::data_dir().unwrap().join("stine-cli").join("secrets.age");
+}
+
+/// Secrets of a profile, which are only stored encrypted
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+#[serde(default)]
+pub(crate) struct ProfileSecrets {
+    pub password: Option<String>,
+    pub email_password: Option<String>,
+    pub session: Option<Session>,
+}
+
+/// Passphrase protected file containing the [`ProfileSecrets`] of all profiles.
+/// Uses the age file format with a scrypt recipient, so it can also be decrypted using `age -d`
+pub(crate) struct SecretStore {
+    path: PathBuf,
+    passphrase: SecretString,
+    /// scrypt work factor `log2(N)`. `None` picks a factor taking about one second
+    work_factor: Option<u8>,
+    pub profiles: BTreeMap<String, ProfileSecrets>,
+}
+
+/// Reads the passphrase of the store from STINE_PASSPHRASE, the passphrase command or a prompt.
+/// `confirm` asks twice when prompting, e.g. when creating a new store
+pub(crate) fn read_passphrase(settings: &Settings, confirm: bool) -> anyhow::Result<SecretString> {
+    if let Some(passphrase) = &settings.passphrase.value {
+        return Ok(SecretString::from(passphrase.as_str()));
+    }
+
+    if let Some(command) = &settings.passphrase_command.value {
+        info!("Reading passphrase using the passphrase command of {}", settings.passphrase_command.source);
+        return Ok(SecretString::from(run_password_command(command)?));
+    }
+
+    if !std::io::stdin().is_terminal() {
+        return Err(anyhow!("No passphrase for the encrypted store. Set STINE_PASSPHRASE or passphrase_command"));
+    }
+
+    let passphrase = rpassword::prompt_password("Passphrase of the encrypted store: ")?;
+    if confirm && rpassword::prompt_password("Repeat passphrase: ")? != passphrase {
+        return Err(anyhow!("Passphrases don't match"));
+    }
+    Ok(SecretString::from(passphrase))
+}
+
+impl SecretStore {
+    /// Decrypts the store at `path`, or creates an empty store if it doesn't exist
+    pub(crate) fn open(path: &Path, passphrase: SecretString) -> anyhow::Result<SecretStore> {
+        let mut store = SecretStore {
+            path: path.to_path_buf(),
+            passphrase,
+            work_factor: None,
+            profiles: BTreeMap::new(),
+        };
+
+        if path.exists() {
+            let ciphertext = fs::read(path)
+                .with_context(|| format!("Failed reading encrypted store {}", path.display()))?;
+            let identity = age::scrypt::Identity::new(SecretString::from(store.passphrase.expose_secret()));
+            let plaintext = age::decrypt(&identity, &ciphertext)
+                .context("Failed decrypting the store. Is the passphrase correct?")?;
+            store.profiles = toml::from_str(&String::from_utf8(plaintext)?)?;
+        }
+
+        Ok(store)
+    }
+
+    pub(crate) fn profile(&self, name: &str) -> ProfileSecrets {
+        self.profiles.get(name).cloned().unwrap_or_default()
+    }
+
+    pub(crate) fn profile_mut(&mut self, name: &str) -> &mut ProfileSecrets {
+        self.profiles.entry(name.to_string()).or_default()
+    }
+
+    /// Encrypts and writes the store. The file is only readable by the current user
+    pub(crate) fn save(&self) -> anyhow::Result<()> {
+        let mut recipient = age::scrypt::Recipient::new(SecretString::from(self.passphrase.expose_secret()));
+        if let Some(work_factor) = self.work_factor {
+            recipient.set_work_factor(work_factor);
+        }
+        let plaintext = toml::to_string_pretty(&self.profiles)?;
+        let ciphertext = age::encrypt(&recipient, plaintext.as_bytes())?;
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options.open(&self.path)
+            .and_then(|mut file| file.write_all(&ciphertext))
+            .with_context(|| format!("Failed writing encrypted store {}", self.path.display()))
+    }
+}
+
+/// Moves passwords of the config and all sessions into the store. Returns the number of moved secrets
+pub(crate) fn move_secrets(config: &mut ConfigFile, sessions: &mut BTreeMap<String, Session>,
+                           store: &mut SecretStore) -> usize {
+    let mut moved = 0;
+    for (name, profile) in &mut config.profile {
+        if let Some(password) = profile.password.take() {
+            store.profile_mut(name).password = Some(password);
+            moved += 1;
+        }
+        if let Some(email_password) = profile.notify.email_password.take() {
+            store.profile_mut(name).email_password = Some(email_password);
+            moved += 1;
+        }
+    }
+    for (name, session) in std::mem::take(sessions) {
+        store.profile_mut(&name).session = Some(session);
+        moved += 1;
+    }
+    moved
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use age::secrecy::SecretString;
+
+    use crate::config::{ConfigFile, Session};
+    use crate::store::{move_secrets, SecretStore};
+
+    #[test]
+    fn test_secret_store() {
+        let path = std::env::temp_dir().join("stine-cli-store-test").join("secrets.age");
+        fs::remove_file(&path).ok();
+
+        let mut store = SecretStore::open(&path, SecretString::from("correct horse")).unwrap();
+        assert!(store.profiles.is_empty());
+        // keep the test fast
+        store.work_factor = Some(2);
+        store.profile_mut("default").password = Some("hunter2".to_string());
+        store.profile_mut("default").session = Some(Session {
+            session: "123456789".to_string(),
+            cnsc_cookie: "abc".to_string(),
+            last_used: Some(1700000000),
+        });
+        store.save().unwrap();
+
+        let content = fs::read(&path).unwrap();
+        assert!(content.starts_with(b"age-encryption.org/v1"));
+        assert!(!String::from_utf8_lossy(&content).contains("hunter2"));
+
+        let store = SecretStore::open(&path, SecretString::from("correct horse")).unwrap();
+        assert_eq!(store.profile("default").password.as_deref(), Some("hunter2"));
+        assert_eq!(store.profile("default").session.unwrap().session, "123456789");
+        assert_eq!(store.profile("other"), Default::default());
+
+        assert!(SecretStore::open(&path, SecretString::from("wrong")).is_err());
+    }
+
+    #[test]
+    fn test_move_secrets() {
+        let mut config: ConfigFile = toml::from_str(r#"
+            [profile.default]
+            username = "baa1234"
+            password = "hunter2"
+
+            [profile.work.notify]
+            email_password = "secret"
+        "#).unwrap();
+        let mut sessions = [("default".to_string(), Session::default())].into();
+        let path = std::env::temp_dir().join("stine-cli-store-test").join("move.age");
+        fs::remove_file(&path).ok();
+        let mut store = SecretStore::open(&path, SecretString::from("correct horse")).unwrap();
+
+        assert_eq!(move_secrets(&mut config, &mut sessions, &mut store), 3);
+        assert!(sessions.is_empty());
+        assert_eq!(config.get_profile("default").password, None);
+        assert_eq!(config.get_profile("default").username.as_deref(), Some("baa1234"));
+        assert_eq!(store.profile("default").password.as_deref(), Some("hunter2"));
+        assert_eq!(store.profile("default").session, Some(Session::default()));
+        assert_eq!(store.profile("work").email_password.as_deref(), Some("secret"));
+    }
+}
@@ -0,0 +1,900 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::io::{self, Stdout};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, Utc};
+use clap::Command;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Row, Table, TableState, Tabs, Wrap};
+use ratatui::{Frame, Terminal};
+
+use stine_rs::{CourseResult, Document, GradeStats, LazyLevel, ModuleCategory, MyRegistrations, RegistrationPeriod,
+               SemesterResult, Stine};
+
+use crate::Backend;
+use crate::grade_stats::{bar_width, top_percent};
+use crate::logging;
+use crate::periods::{format_duration, PeriodEntry, PeriodStatus};
+
+// how often pressed keys and loaded data are checked
+const TICK: Duration = Duration::from_millis(100);
+
+const LOADER_THREAD: &str = "stine-loader";
+
+pub(crate) fn tui_subcommand() -> Command {
+    Command::new("tui")
+        .about("Browse results, registrations, courses, the timetable, documents and registration periods \
+        in a full-screen terminal UI")
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Tab {
+    Results,
+    Registrations,
+    Catalog,
+    Timetable,
+    Documents,
+    Periods,
+}
+
+impl Tab {
+    const ALL: [Tab; 6] = [Tab::Results, Tab::Registrations, Tab::Catalog, Tab::Timetable, Tab::Documents, Tab::Periods];
+
+    fn title(self) -> &'static str {
+        match self {
+            Tab::Results => "Results",
+            Tab::Registrations => "Registrations",
+            Tab::Catalog => "Courses",
+            Tab::Timetable => "Timetable",
+            Tab::Documents => "Documents",
+            Tab::Periods => "Periods",
+        }
+    }
+
+    fn index(self) -> usize {
+        Tab::ALL.iter().position(|tab| *tab == self).unwrap()
+    }
+}
+
+/// Data requested from the background thread
+enum Request {
+    Results,
+    GradeStats(Box<CourseResult>),
+    Registrations,
+    Catalog { force_reload: bool },
+    /// Appointments of the week starting at the monday
+    Timetable(NaiveDate),
+    Documents,
+    Periods,
+}
+
+/// Data loaded by the background thread
+enum Loaded {
+    Results(anyhow::Result<Vec<SemesterResult>>),
+    /// Grade stats of the course with the number
+    GradeStats(String, anyhow::Result<Option<GradeStats>>),
+    Registrations(anyhow::Result<MyRegistrations>),
+    Catalog(anyhow::Result<Vec<ModuleCategory>>),
+    Timetable(NaiveDate, anyhow::Result<Vec<TimetableEntry>>),
+    Documents(anyhow::Result<Vec<Document>>),
+    Periods(anyhow::Result<Vec<RegistrationPeriod>>),
+}
+
+/// Appointment of a registered course
+#[derive(Debug, Clone, PartialEq)]
+struct TimetableEntry {
+    from: DateTime<Utc>,
+    to: Option<DateTime<Utc>>,
+    course: String,
+    room: String,
+}
+
+enum State<T> {
+    NotLoaded,
+    Loading,
+    Loaded(T),
+    Failed(String),
+}
+
+impl<T> State<T> {
+    fn from_result(result: anyhow::Result<T>) -> Self {
+        match result {
+            Ok(data) => State::Loaded(data),
+            Err(err) => State::Failed(format!("{err:#}")),
+        }
+    }
+
+    fn loaded(&self) -> Option<&T> {
+        match self {
+            State::Loaded(data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+fn load_timetable(stine: &mut Stine, backend: &dyn stine_rs::Backend, monday: NaiveDate)
+                  -> anyhow::Result<Vec<TimetableEntry>> {
+    // appointments are mapped by course id, the registered events contain their names
+    let names: HashMap<String, String> = backend.get_events(stine, LazyLevel::FullLazy)
+        .map(|events| events.into_iter()
+            .map(|submodule| (submodule.id, submodule.name))
+            .collect())
+        .unwrap_or_default();
+
+    let appointments = backend.get_appointments(stine, monday..=monday + Days::new(6))?;
+    let mut entries: Vec<TimetableEntry> = appointments.into_iter()
+        .flat_map(|(id, appointments)| {
+            let course = names.get(&id).cloned().unwrap_or(id);
+            appointments.into_iter().filter_map(move |appointment| Some(TimetableEntry {
+                from: appointment.from?,
+                to: appointment.to,
+                course: course.clone(),
+                room: appointment.room,
+            }))
+        })
+        .collect();
+    entries.sort_by_key(|entry| entry.from);
+    Ok(entries)
+}
+
+fn load(stine: &mut Stine, backend: &dyn stine_rs::Backend, request: &Request) -> Loaded {
+    match request {
+        Request::Results => Loaded::Results(backend.get_all_semester_results(stine, LazyLevel::FullLazy)),
+        Request::GradeStats(course) => Loaded::GradeStats(
+            course.number.clone(), Ok(course.as_ref().clone().get_grade_stats(stine))),
+        Request::Registrations => Loaded::Registrations(backend.get_my_registrations(stine, LazyLevel::FullLazy)),
+        Request::Catalog { force_reload } => Loaded::Catalog(
+            stine.get_registration_modules(*force_reload, false, LazyLevel::FullLazy)),
+        Request::Timetable(monday) => Loaded::Timetable(*monday, load_timetable(stine, backend, *monday)),
+        Request::Documents => Loaded::Documents(stine.get_documents().map_err(Into::into)),
+        Request::Periods => Loaded::Periods(stine.get_registration_periods().map_err(Into::into)),
+    }
+}
+
+impl Request {
+    /// Failed result of this request
+    fn failed(&self, err: anyhow::Error) -> Loaded {
+        match self {
+            Request::Results => Loaded::Results(Err(err)),
+            Request::GradeStats(course) => Loaded::GradeStats(course.number.clone(), Err(err)),
+            Request::Registrations => Loaded::Registrations(Err(err)),
+            Request::Catalog { .. } => Loaded::Catalog(Err(err)),
+            Request::Timetable(monday) => Loaded::Timetable(*monday, Err(err)),
+            Request::Documents => Loaded::Documents(Err(err)),
+            Request::Periods => Loaded::Periods(Err(err)),
+        }
+    }
+}
+
+/// Lines of the module tree with their depth. If `search` is set, only matching entries and their parents are kept
+fn catalog_lines(categories: &[ModuleCategory], search: &str) -> Vec<(usize, String)> {
+    let search = search.to_lowercase();
+    let matches = |text: &str| text.to_lowercase().contains(&search);
+
+    let mut lines = Vec::new();
+    for category in categories {
+        let category_matches = matches(&category.name);
+
+        let mut children = Vec::new();
+        for module in &category.modules {
+            let module_name = module.to_string();
+            let module_matches = category_matches || matches(&module_name);
+            let submodules: Vec<(usize, String)> = module.sub_modules.iter()
+                .filter(|submodule| module_matches || matches(&submodule.name))
+                .map(|submodule| (2, submodule.name.clone()))
+                .collect();
+
+            if module_matches || !submodules.is_empty() {
+                children.push((1, module_name));
+                children.extend(submodules);
+            }
+        }
+        children.extend(category.orphan_submodules.iter()
+            .filter(|submodule| category_matches || matches(&submodule.name))
+            .map(|submodule| (1, submodule.name.clone())));
+
+        if category_matches || !children.is_empty() {
+            lines.push((0, category.name.clone()));
+            lines.extend(children);
+        }
+    }
+    lines
+}
+
+/// Monday of the week containing `date`
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Days::new(u64::from(date.weekday().num_days_from_monday()))
+}
+
+fn format_local(datetime: DateTime<Utc>, format: &str) -> String {
+    DateTime::<Local>::from(datetime).format(format).to_string()
+}
+
+struct App {
+    tab: Tab,
+    requests: Sender<Request>,
+    results: State<Vec<SemesterResult>>,
+    /// Drill-down of the selected course of the results
+    grade_stats: Option<(CourseResult, State<Option<GradeStats>>)>,
+    registrations: State<MyRegistrations>,
+    catalog: State<Vec<ModuleCategory>>,
+    search: String,
+    searching: bool,
+    week: NaiveDate,
+    timetable: State<Vec<TimetableEntry>>,
+    documents: State<Vec<Document>>,
+    periods: State<Vec<RegistrationPeriod>>,
+    /// Selected row of every tab
+    selected: [usize; 6],
+    quit: bool,
+}
+
+impl App {
+    fn new(requests: Sender<Request>) -> Self {
+        App {
+            tab: Tab::Results,
+            requests,
+            results: State::NotLoaded,
+            grade_stats: None,
+            registrations: State::NotLoaded,
+            catalog: State::NotLoaded,
+            search: String::new(),
+            searching: false,
+            week: week_start(Local::now().date_naive()),
+            timetable: State::NotLoaded,
+            documents: State::NotLoaded,
+            periods: State::NotLoaded,
+            selected: [0; 6],
+            quit: false,
+        }
+    }
+
+    fn request(&self, request: Request) {
+        // fails only if the background thread died, which leaves the tab loading
+        self.requests.send(request).ok();
+    }
+
+    /// Requests the data of the current tab, if it wasn't loaded yet or `reload` is set
+    fn load_tab(&mut self, reload: bool) {
+        macro_rules! load {
+            ($state:expr, $request:expr) => {
+                if reload || matches!($state, State::NotLoaded) {
+                    $state = State::Loading;
+                    self.request($request);
+                }
+            };
+        }
+
+        match self.tab {
+            Tab::Results => load!(self.results, Request::Results),
+            Tab::Registrations => load!(self.registrations, Request::Registrations),
+            Tab::Catalog => load!(self.catalog, Request::Catalog { force_reload: reload }),
+            Tab::Timetable => load!(self.timetable, Request::Timetable(self.week)),
+            Tab::Documents => load!(self.documents, Request::Documents),
+            Tab::Periods => load!(self.periods, Request::Periods),
+        }
+    }
+
+    fn select_tab(&mut self, tab: Tab) {
+        self.tab = tab;
+        self.load_tab(false);
+    }
+
+    fn handle_loaded(&mut self, loaded: Loaded) {
+        match loaded {
+            Loaded::Results(result) => self.results = State::from_result(result),
+            // ignore courses which were closed while loading
+            Loaded::GradeStats(number, result) => if let Some((course, state)) = &mut self.grade_stats {
+                if course.number == number {
+                    *state = State::from_result(result);
+                }
+            },
+            Loaded::Registrations(result) => self.registrations = State::from_result(result),
+            Loaded::Catalog(result) => self.catalog = State::from_result(result),
+            // ignore weeks which were skipped while loading
+            Loaded::Timetable(week, result) => if week == self.week {
+                self.timetable = State::from_result(result);
+            },
+            Loaded::Documents(result) => self.documents = State::from_result(result),
+            Loaded::Periods(result) => self.periods = State::from_result(result),
+        }
+    }
+
+    fn courses(&self) -> Vec<(&SemesterResult, &CourseResult)> {
+        self.results.loaded().map(|results| results.iter()
+            .flat_map(|semester| semester.courses.iter().map(move |course| (semester, course)))
+            .collect())
+            .unwrap_or_default()
+    }
+
+    fn registration_lines(&self) -> Vec<(String, Color)> {
+        let Some(registrations) = self.registrations.loaded() else {
+            return vec![];
+        };
+
+        let groups = [
+            ("Pending", Color::Yellow, registrations.pending_submodules.iter().map(|s| s.name.clone()).collect::<Vec<_>>()),
+            ("Accepted", Color::Green, registrations.accepted_submodules.iter().map(|s| s.name.clone()).collect()),
+            ("Rejected", Color::Red, registrations.rejected_submodules.iter().map(|s| s.name.clone()).collect()),
+            ("Accepted modules", Color::Green, registrations.accepted_modules.iter().map(|m| m.to_string()).collect()),
+        ];
+
+        let mut lines = Vec::new();
+        for (title, color, names) in groups {
+            lines.push((format!("── {title} ({}) ──", names.len()), color));
+            lines.extend(names.into_iter().map(|name| (format!("  {name}"), Color::Reset)));
+        }
+        lines
+    }
+
+    /// Number of selectable rows of the current tab
+    fn row_count(&self) -> usize {
+        match self.tab {
+            Tab::Results => self.courses().len(),
+            Tab::Registrations => self.registration_lines().len(),
+            Tab::Catalog => self.catalog.loaded().map_or(0, |c| catalog_lines(c, &self.search).len()),
+            Tab::Timetable => self.timetable.loaded().map_or(0, Vec::len),
+            Tab::Documents => self.documents.loaded().map_or(0, Vec::len),
+            Tab::Periods => self.periods.loaded().map_or(0, Vec::len),
+        }
+    }
+
+    fn move_selection(&mut self, offset: isize) {
+        let max = self.row_count().saturating_sub(1);
+        let selected = &mut self.selected[self.tab.index()];
+        *selected = selected.saturating_add_signed(offset).min(max);
+    }
+
+    fn handle_search_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => {
+                self.search.clear();
+                self.searching = false;
+            }
+            KeyCode::Enter => self.searching = false,
+            KeyCode::Backspace => {
+                self.search.pop();
+            }
+            KeyCode::Char(c) => self.search.push(c),
+            _ => return,
+        }
+        self.selected[Tab::Catalog.index()] = 0;
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+        if self.searching {
+            self.handle_search_key(key);
+            return;
+        }
+        if self.grade_stats.is_some() {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q')) {
+                self.grade_stats = None;
+            }
+            return;
+        }
+
+        let tab_index = self.tab.index();
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Tab | KeyCode::Right | KeyCode::Char('l') =>
+                self.select_tab(Tab::ALL[(tab_index + 1) % Tab::ALL.len()]),
+            KeyCode::BackTab | KeyCode::Left | KeyCode::Char('h') =>
+                self.select_tab(Tab::ALL[(tab_index + Tab::ALL.len() - 1) % Tab::ALL.len()]),
+            KeyCode::Char(c @ '1'..='6') => self.select_tab(Tab::ALL[c as usize - '1' as usize]),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::PageDown => self.move_selection(10),
+            KeyCode::PageUp => self.move_selection(-10),
+            KeyCode::Home | KeyCode::Char('g') => self.selected[tab_index] = 0,
+            KeyCode::End | KeyCode::Char('G') => self.selected[tab_index] = self.row_count().saturating_sub(1),
+            KeyCode::Char('r') => self.load_tab(true),
+            KeyCode::Esc if self.tab == Tab::Catalog => self.search.clear(),
+            KeyCode::Char('/') if self.tab == Tab::Catalog => self.searching = true,
+            KeyCode::Enter if self.tab == Tab::Results => {
+                let course = self.courses().get(self.selected[tab_index]).map(|(_, course)| (*course).clone());
+                if let Some(course) = course {
+                    self.request(Request::GradeStats(Box::new(course.clone())));
+                    self.grade_stats = Some((course, State::Loading));
+                }
+            }
+            KeyCode::Char('n') | KeyCode::Char('p') if self.tab == Tab::Timetable => {
+                self.week = if key.code == KeyCode::Char('n') {
+                    self.week + Days::new(7)
+                } else {
+                    self.week - Days::new(7)
+                };
+                self.selected[tab_index] = 0;
+                self.load_tab(true);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Shows that the data is loading or failed. `None` if it's loaded
+fn placeholder<T>(state: &State<T>) -> Option<Paragraph<'static>> {
+    match state {
+        State::NotLoaded | State::Loading => Some(Paragraph::new("Loading…".italic())),
+        State::Failed(err) => Some(Paragraph::new(format!("Failed loading: {err}").red()).wrap(Wrap { trim: true })),
+        State::Loaded(_) => None,
+    }
+}
+
+fn highlight_style() -> Style {
+    Style::default().add_modifier(Modifier::REVERSED)
+}
+
+fn draw_table(frame: &mut Frame, area: Rect, block: Block, header: Row, rows: Vec<Row>, widths: &[Constraint],
+              selected: usize) {
+    let table = Table::new(rows, widths.to_vec())
+        .header(header.bold())
+        .block(block)
+        .highlight_style(highlight_style());
+    let mut state = TableState::default().with_selected(Some(selected));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+fn draw_list(frame: &mut Frame, area: Rect, block: Block, items: Vec<ListItem>, selected: usize) {
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(highlight_style());
+    let mut state = ListState::default().with_selected(Some(selected));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_results(frame: &mut Frame, area: Rect, app: &App) {
+    let block = Block::default().borders(Borders::ALL).title("Results");
+    if let Some(placeholder) = placeholder(&app.results) {
+        frame.render_widget(placeholder.block(block), area);
+        return;
+    }
+
+    let rows = app.courses().into_iter().map(|(semester, course)| Row::new([
+        semester.semester.to_string(),
+        course.number.clone(),
+        course.name.clone(),
+        course.final_grade.map_or("-".to_string(), |grade| grade.to_string()),
+        course.credits.clone().unwrap_or("-".to_string()),
+        course.status.clone(),
+    ])).collect();
+    let widths = [Constraint::Length(12), Constraint::Length(10), Constraint::Fill(1),
+        Constraint::Length(6), Constraint::Length(8), Constraint::Length(16)];
+    draw_table(frame, area, block, Row::new(["Semester", "ID", "Name", "Grade", "Credits", "Status"]), rows,
+               &widths, app.selected[Tab::Results.index()]);
+}
+
+/// Popup in the middle of `area`
+fn popup_area(area: Rect, width_percent: u16, height_percent: u16) -> Rect {
+    let [_, vertical, _] = Layout::vertical([
+        Constraint::Percentage((100 - height_percent) / 2),
+        Constraint::Percentage(height_percent),
+        Constraint::Percentage((100 - height_percent) / 2),
+    ]).areas(area);
+    let [_, popup, _] = Layout::horizontal([
+        Constraint::Percentage((100 - width_percent) / 2),
+        Constraint::Percentage(width_percent),
+        Constraint::Percentage((100 - width_percent) / 2),
+    ]).areas(vertical);
+    popup
+}
+
+fn grade_stats_lines(course: &CourseResult, stats: &GradeStats) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    if let Some(average) = stats.average {
+        lines.push(Line::from(format!("Average: {average:.2}")).bold());
+    }
+    lines.push(Line::default());
+
+    let max_count = stats.grade_map.iter().map(|(_, count)| *count).max().unwrap_or_default();
+    for (grade, count) in &stats.grade_map {
+        let line = format!("{grade:>4.1} │ {} {count}", "█".repeat(bar_width(*count, max_count)));
+        if course.final_grade.is_some_and(|own| (own - grade).abs() < f32::EPSILON) {
+            lines.push(Line::from(format!("{line} ◀ you")).green().bold());
+        } else {
+            lines.push(Line::from(line));
+        }
+    }
+
+    if let Some(top) = course.final_grade.and_then(|grade| top_percent(&stats.grade_map, grade)) {
+        lines.push(Line::default());
+        lines.push(Line::from(format!("Your grade is in the top {top:.1}%")).green());
+    }
+    lines
+}
+
+fn draw_grade_stats(frame: &mut Frame, area: Rect, course: &CourseResult, state: &State<Option<GradeStats>>) {
+    let area = popup_area(area, 70, 70);
+    let block = Block::default().borders(Borders::ALL)
+        .title(format!("Grade stats of {} {}", course.number, course.name))
+        .title_bottom("Esc: close");
+    frame.render_widget(Clear, area);
+
+    let paragraph = match state {
+        State::Loaded(Some(stats)) => Paragraph::new(grade_stats_lines(course, stats)),
+        State::Loaded(None) => Paragraph::new("STINE has no grade stats for this course"),
+        _ => placeholder(state).unwrap(),
+    };
+    frame.render_widget(paragraph.block(block), area);
+}
+
+fn draw_registrations(frame: &mut Frame, area: Rect, app: &App) {
+    let block = Block::default().borders(Borders::ALL).title("Registrations");
+    if let Some(placeholder) = placeholder(&app.registrations) {
+        frame.render_widget(placeholder.block(block), area);
+        return;
+    }
+
+    let items = app.registration_lines().into_iter()
+        .map(|(line, color)| ListItem::new(line).fg(color))
+        .collect();
+    draw_list(frame, area, block, items, app.selected[Tab::Registrations.index()]);
+}
+
+fn draw_catalog(frame: &mut Frame, area: Rect, app: &App) {
+    let title = if app.searching || !app.search.is_empty() {
+        format!("Courses — search: {}{}", app.search, if app.searching { "▏" } else { "" })
+    } else {
+        "Courses".to_string()
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let Some(categories) = app.catalog.loaded() else {
+        frame.render_widget(placeholder(&app.catalog).unwrap().block(block), area);
+        return;
+    };
+
+    let items = catalog_lines(categories, &app.search).into_iter()
+        .map(|(depth, name)| {
+            let item = ListItem::new(format!("{}{name}", "  ".repeat(depth)));
+            match depth {
+                0 => item.bold(),
+                1 => item.fg(Color::LightBlue),
+                _ => item,
+            }
+        })
+        .collect();
+    draw_list(frame, area, block, items, app.selected[Tab::Catalog.index()]);
+}
+
+fn draw_timetable(frame: &mut Frame, area: Rect, app: &App) {
+    let block = Block::default().borders(Borders::ALL)
+        .title(format!("Timetable — week of {}", app.week.format("%d.%m.%Y")));
+    let Some(entries) = app.timetable.loaded() else {
+        frame.render_widget(placeholder(&app.timetable).unwrap().block(block), area);
+        return;
+    };
+    if entries.is_empty() {
+        frame.render_widget(Paragraph::new("No appointments this week").block(block), area);
+        return;
+    }
+
+    let mut previous_day = None;
+    let rows = entries.iter().map(|entry| {
+        let day = format_local(entry.from, "%a %d.%m.");
+        // the day is only shown for its first appointment
+        let day_cell = if previous_day.as_ref() == Some(&day) { String::new() } else { day.clone() };
+        previous_day = Some(day);
+        let time = match entry.to {
+            Some(to) => format!("{}-{}", format_local(entry.from, "%H:%M"), format_local(to, "%H:%M")),
+            None => format_local(entry.from, "%H:%M"),
+        };
+        Row::new([day_cell, time, entry.course.clone(), entry.room.clone()])
+    }).collect();
+    let widths = [Constraint::Length(11), Constraint::Length(12), Constraint::Fill(2), Constraint::Fill(1)];
+    draw_table(frame, area, block, Row::new(["Day", "Time", "Course", "Room"]), rows, &widths,
+               app.selected[Tab::Timetable.index()]);
+}
+
+fn draw_documents(frame: &mut Frame, area: Rect, app: &App) {
+    let block = Block::default().borders(Borders::ALL).title("Documents");
+    let Some(documents) = app.documents.loaded() else {
+        frame.render_widget(placeholder(&app.documents).unwrap().block(block), area);
+        return;
+    };
+
+    let rows = documents.iter().map(|document| Row::new([
+        format_local(document.datetime, "%Y-%m-%d %H:%M"),
+        document.name.clone(),
+        document.status.clone().unwrap_or_default(),
+    ])).collect();
+    let widths = [Constraint::Length(16), Constraint::Fill(2), Constraint::Fill(1)];
+    draw_table(frame, area, block, Row::new(["Date", "Name", "Status"]), rows, &widths,
+               app.selected[Tab::Documents.index()]);
+}
+
+fn draw_periods(frame: &mut Frame, area: Rect, app: &App) {
+    let block = Block::default().borders(Borders::ALL).title("Registration periods");
+    let Some(periods) = app.periods.loaded() else {
+        frame.render_widget(placeholder(&app.periods).unwrap().block(block), area);
+        return;
+    };
+
+    let now = Utc::now();
+    let rows = periods.iter().map(|period| {
+        let entry = PeriodEntry::new(period, now);
+        let status = match entry.status {
+            PeriodStatus::Upcoming => format!("starts in {}", format_duration(entry.start - now)),
+            PeriodStatus::Active => format!("current, ends in {}", format_duration(entry.end - now)),
+            PeriodStatus::Over => "over".to_string(),
+        };
+        let row = Row::new([
+            entry.name.clone(),
+            format_local(entry.start, "%a %Y-%m-%d %H:%M"),
+            format_local(entry.end, "%a %Y-%m-%d %H:%M"),
+            status,
+        ]);
+        match entry.status {
+            PeriodStatus::Upcoming => row.yellow(),
+            PeriodStatus::Active => row.green().bold(),
+            PeriodStatus::Over => row.dim(),
+        }
+    }).collect();
+    let widths = [Constraint::Fill(1), Constraint::Length(20), Constraint::Length(20), Constraint::Fill(1)];
+    draw_table(frame, area, block, Row::new(["Period", "Start", "End", "Status"]), rows, &widths,
+               app.selected[Tab::Periods.index()]);
+}
+
+fn help_line(app: &App) -> &'static str {
+    if app.searching {
+        return "type to search · Enter: done · Esc: clear";
+    }
+    if app.grade_stats.is_some() {
+        return "Esc: close";
+    }
+    match app.tab {
+        Tab::Results => "←/→: tab · ↑/↓: select · Enter: grade stats · r: reload · q: quit",
+        Tab::Catalog => "←/→: tab · ↑/↓: select · /: search · r: reload from STINE · q: quit",
+        Tab::Timetable => "←/→: tab · ↑/↓: select · n/p: next/previous week · r: reload · q: quit",
+        _ => "←/→: tab · ↑/↓: select · r: reload · q: quit",
+    }
+}
+
+fn draw(frame: &mut Frame, app: &App) {
+    let [tabs_area, body, footer] = Layout::vertical([
+        Constraint::Length(3), Constraint::Fill(1), Constraint::Length(1),
+    ]).areas(frame.size());
+
+    let titles = Tab::ALL.iter().enumerate().map(|(i, tab)| format!("{} {}", i + 1, tab.title()));
+    let tabs = Tabs::new(titles)
+        .block(Block::default().borders(Borders::ALL).title("stine-cli"))
+        .select(app.tab.index())
+        .highlight_style(Style::default().bold().fg(Color::Green));
+    frame.render_widget(tabs, tabs_area);
+
+    match app.tab {
+        Tab::Results => draw_results(frame, body, app),
+        Tab::Registrations => draw_registrations(frame, body, app),
+        Tab::Catalog => draw_catalog(frame, body, app),
+        Tab::Timetable => draw_timetable(frame, body, app),
+        Tab::Documents => draw_documents(frame, body, app),
+        Tab::Periods => draw_periods(frame, body, app),
+    }
+    if let Some((course, state)) = &app.grade_stats {
+        draw_grade_stats(frame, body, course, state);
+    }
+
+    frame.render_widget(Paragraph::new(help_line(app)).dim(), footer);
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload.downcast_ref::<&str>().copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("panic")
+}
+
+fn restore_terminal() -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen)
+}
+
+fn run_app(terminal: &mut Terminal<CrosstermBackend<Stdout>>, requests: Sender<Request>, loaded: Receiver<Loaded>)
+           -> io::Result<()> {
+    let mut app = App::new(requests);
+    app.load_tab(false);
+
+    while !app.quit {
+        while let Ok(data) = loaded.try_recv() {
+            app.handle_loaded(data);
+        }
+        terminal.draw(|frame| draw(frame, &app))?;
+
+        if event::poll(TICK)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.handle_key(key);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs the terminal UI. Data is loaded by a background thread, so the UI stays responsive
+pub(crate) fn tui_command(stine: &mut Stine, backend: Backend) {
+    enable_raw_mode().context("Failed enabling raw mode of the terminal").unwrap();
+    execute!(io::stdout(), EnterAlternateScreen).context("Failed switching to the alternate screen").unwrap();
+    // log records would garble the UI, they are still written to the log file
+    logging::mute_terminal(true);
+
+    // panics would leave the terminal unusable otherwise.
+    // Panics of the loader are shown in the tab instead, as printing them would garble the UI
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if thread::current().name() != Some(LOADER_THREAD) {
+            logging::mute_terminal(false);
+            restore_terminal().ok();
+            default_hook(info);
+        }
+    }));
+
+    let (request_sender, request_receiver) = mpsc::channel::<Request>();
+    let (loaded_sender, loaded_receiver) = mpsc::channel::<Loaded>();
+
+    let result = thread::scope(|scope| {
+        let loader = thread::Builder::new().name(LOADER_THREAD.to_string()).spawn_scoped(scope, move || {
+            let backend = backend.get();
+            for request in request_receiver {
+                let loaded = panic::catch_unwind(AssertUnwindSafe(|| load(stine, backend.as_ref(), &request)))
+                    .unwrap_or_else(|payload| request.failed(anyhow!("Unexpected error: {}", panic_message(&*payload))));
+                // stops once the UI was closed
+                if loaded_sender.send(loaded).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let result = loader.as_ref().map_err(|err| io::Error::new(err.kind(), err.to_string()))
+            .and_then(|_| Terminal::new(CrosstermBackend::new(io::stdout())))
+            .and_then(|mut terminal| run_app(&mut terminal, request_sender, loaded_receiver));
+
+        // the scope waits for the loader, the terminal must not stay frozen in the meantime
+        logging::mute_terminal(false);
+        restore_terminal().context("Failed restoring the terminal").unwrap();
+        if loader.is_ok_and(|loader| !loader.is_finished()) {
+            eprintln!("Waiting for the running request to finish...");
+        }
+        result
+    });
+
+    // back to the default hook, e.g. for the shell
+    drop(panic::take_hook());
+    result.context("Failed drawing the terminal UI").unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use chrono::{NaiveDate, Utc};
+    use crossterm::event::{KeyCode, KeyEvent};
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use serde_json::json;
+
+    use stine_rs::{CourseResult, Document, ModuleCategory, Semester, SemesterResult};
+
+    use crate::tui::{App, catalog_lines, draw, Loaded, Request, State, Tab, week_start};
+
+    fn submodule(name: &str) -> serde_json::Value {
+        let unloaded = json!({"status": "Unloaded", "link": ""});
+        json!({"id": "1", "course_number": "", "name": name,
+            "info": unloaded, "appointments": unloaded, "groups": unloaded})
+    }
+
+    fn module(number: &str, name: &str, submodules: Vec<serde_json::Value>) -> serde_json::Value {
+        json!({"module_number": number, "name": name, "sub_modules": submodules, "exams": [], "owner": "",
+            "timetable_name": null, "duration": null, "electives": null, "credits": null, "start_semester": null,
+            "attributes": {}})
+    }
+
+    #[test]
+    fn test_catalog_lines() {
+        let categories: Vec<ModuleCategory> = serde_json::from_value(json!([
+            {"name": "Pflichtmodule", "orphan_submodules": [submodule("64-999 Orientierungseinheit")], "modules": [
+                module("InfB-SE1", "Softwareentwicklung I", vec![submodule("64-040 Vorlesung SE1"), submodule("64-041 Übung SE1")]),
+                module("InfB-GDB", "Grundlagen von Datenbanken", vec![submodule("64-060 Vorlesung GDB")]),
+            ]},
+            {"name": "Wahlpflichtmodule", "orphan_submodules": [], "modules": [
+                module("InfB-BV", "Bildverarbeitung", vec![]),
+            ]},
+        ])).unwrap();
+
+        assert_eq!(catalog_lines(&categories, "").len(), 9);
+
+        assert_eq!(catalog_lines(&categories, "übung"), vec![
+            (0, "Pflichtmodule".to_string()),
+            (1, "InfB-SE1 - Softwareentwicklung I".to_string()),
+            (2, "64-041 Übung SE1".to_string()),
+        ]);
+
+        // matching modules include all submodules
+        assert_eq!(catalog_lines(&categories, "infb-se1").len(), 4);
+        // matching categories include everything
+        assert_eq!(catalog_lines(&categories, "wahlpflicht"), vec![
+            (0, "Wahlpflichtmodule".to_string()),
+            (1, "InfB-BV - Bildverarbeitung".to_string()),
+        ]);
+        assert!(catalog_lines(&categories, "nothing").is_empty());
+    }
+
+    #[test]
+    fn test_draw() {
+        let (sender, receiver) = mpsc::channel();
+        let mut app = App::new(sender);
+        app.load_tab(false);
+        assert!(matches!(receiver.try_recv(), Ok(Request::Results)));
+
+        app.handle_key(KeyEvent::from(KeyCode::Char('5')));
+        assert_eq!(app.tab, Tab::Documents);
+        assert!(matches!(receiver.try_recv(), Ok(Request::Documents)));
+        app.handle_loaded(Loaded::Documents(Ok(vec![Document {
+            name: "Immatrikulationsbescheinigung".to_string(),
+            datetime: Utc::now(),
+            status: None,
+            download: String::new(),
+        }])));
+        app.handle_key(KeyEvent::from(KeyCode::Down));
+        assert_eq!(app.selected[Tab::Documents.index()], 0);
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let content: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+        assert!(content.contains("5 Documents"));
+        assert!(content.contains("Immatrikulationsbescheinigung"));
+
+        app.handle_key(KeyEvent::from(KeyCode::Char('q')));
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn test_stale_grade_stats() {
+        let (sender, receiver) = mpsc::channel();
+        let mut app = App::new(sender);
+        let course = |number: &str| -> CourseResult {
+            serde_json::from_value(json!({
+                "number": number, "name": "Course", "final_grade": 1.3, "credits": null, "status": "bestanden",
+                "grade_stats": null,
+            })).unwrap()
+        };
+        app.handle_loaded(Loaded::Results(Ok(vec![SemesterResult {
+            semester: Semester::new_winter(23, 24),
+            courses: vec![course("64-040"), course("64-074")],
+            semester_gpa: Ok(1.3),
+            semester_credits: String::new(),
+        }])));
+
+        // open the first course, close it and open the second one
+        app.handle_key(KeyEvent::from(KeyCode::Enter));
+        app.handle_key(KeyEvent::from(KeyCode::Esc));
+        app.handle_key(KeyEvent::from(KeyCode::Down));
+        app.handle_key(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(receiver.try_iter().count(), 2);
+
+        app.handle_loaded(Loaded::GradeStats("64-040".to_string(), Ok(None)));
+        assert!(matches!(app.grade_stats, Some((_, State::Loading))));
+        app.handle_loaded(Loaded::GradeStats("64-074".to_string(), Ok(None)));
+        assert!(matches!(app.grade_stats, Some((_, State::Loaded(None)))));
+    }
+
+    #[test]
+    fn test_week_start() {
+        let monday = NaiveDate::from_ymd_opt(2024, 10, 14).unwrap();
+        assert_eq!(week_start(monday), monday);
+        assert_eq!(week_start(NaiveDate::from_ymd_opt(2024, 10, 20).unwrap()), monday);
+        assert_eq!(week_start(NaiveDate::from_ymd_opt(2024, 10, 21).unwrap()), NaiveDate::from_ymd_opt(2024, 10, 21).unwrap());
+    }
+}